@@ -0,0 +1,1157 @@
+//! Benchmark parameters, read from an optional TOML file
+//! and overridden by the command line
+
+use std::convert::TryFrom;
+use std::{fmt, fs, io};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use toml;
+
+use report::Format;
+use stats::OutlierFilter;
+use timer::Timing;
+
+pub static USAGE: &str = "\
+Usage: gl-bench [OPTIONS]
+
+Options:
+    --config PATH       TOML file with the parameters (default: gl-bench.toml)
+    --queries N         number of timed frames per test (default: 200)
+    --warmup N          frames discarded at the start of a test (default: 40)
+    --cooldown N        frames discarded at the end of a test (default: 0)
+    --rejects N         instances drawn by the rejection tests (default: 20)
+    --overdraw N        instances drawn by the fill tests (default: 1)
+    --clear-color R,G,B,A
+                        clear color of the color and depth test (default: 0.3,0.3,0.3,1)
+    --clear-depth Z     depth the tests clear to, from 0 to 1 (default: 1)
+    --depth-func F      depth comparison: never, less, equal, lequal, greater,
+                        notequal, gequal or always (default: less)
+    --clear-scissored   restrict clears to a scissor rectangle
+    --scissor X,Y,W,H   restrict clears to the given rectangle (default: a quarter
+                        of the screen)
+    --windowed WxH      run in a window of the given size instead of full-screen,
+                        resizing it takes effect from the next test
+    --monitor N         index of the monitor to run full-screen on (default: primary)
+    --headless          render offscreen, without a window or a display
+    --resolution WxH    size of the offscreen render target (default: 1920x1080)
+    --fbo-resolution WxH
+                        render to a framebuffer of the given size instead of the window's
+    --gles              request an OpenGL ES 3.0 context instead of desktop GL
+    --profile P         desktop GL profile: core or compat (default: driver's choice)
+    --debug-gl          request a debug context and print the driver's messages
+    --vsync             synchronize the swaps with the display refresh
+    --msaa N            request N samples per pixel, a power of two (default: off)
+    --percentiles LIST  comma-separated percentiles to report (default: 50,95,99)
+    --max-duration SECS stop a test early once it ran for SECS seconds
+    --outlier-filter F  how noisy frames are dropped: warmup or iqr (default: warmup)
+    --timing T          timer queries: elapsed, or timestamp to also split the
+                        clear and draw times of every frame (default: elapsed)
+    --primitive P       also measure fill with points or lines (default: triangles)
+    --point-size N      size of the points in pixels (default: 16)
+    --line-width N      width of the lines in pixels (default: 1)
+    --tris-per-draw N   also measure fill with a grid of about N triangles per instance
+    --attribs           also measure the triangle grid fetching vertex attributes
+    --stream MODE       also measure uploading the attributes of the triangle grid every
+                        frame before drawing it, by orphaning the buffer, mapping it with
+                        invalidation, or through a persistent mapping (GL 4.4) with
+                        fences, compared with orphaning: orphan, map or persistent
+    --blend             also measure alpha-blended fill
+    --blend-layers N    blended instances drawn per frame (default: 4)
+    --texture           also measure fill sampling a texture
+    --texture-size N    width and height of the texture (default: 1024)
+    --filter F          texture filtering: nearest or linear (default: linear)
+    --aniso LEVELS      also measure sampling a mipmapped texture at a grazing angle
+                        with each of the comma-separated anisotropy levels
+    --cubemap SIZE      also measure sampling a cubemap with SIZExSIZE faces
+    --texture-3d SIZE   also measure sampling a SIZExSIZExSIZE 3D texture, filtered
+                        trilinearly unless '--filter nearest'
+    --texture-array N   also measure sampling an array of N textures, switching layers
+                        between tiles of pixels, compared with '--texture' if measured
+    --color-format F    format of the offscreen color attachments and of the texture:
+                        rgba8, rgba16f or r11f_g11f_b10f (default: rgba8)
+    --srgb              also measure the color test with the sRGB encode enabled
+    --hdr               also measure the color test into an RGBA16F framebuffer
+    --wireframe         also measure the color test with the triangles drawn as lines
+    --cull              also measure front and back-facing triangles, with and
+                        without back-face culling
+    --depth-only        also measure fill writing only depth, like a shadow map
+    --render-to-texture also measure fill into a texture, checking that it was drawn
+    --mrt N             also measure fill into N render targets at once
+    --stencil           also measure stencil rejection
+    --stencil-reject F  fraction of the screen failing the stencil test (default: 0.5)
+    --discard F         also measure a shader discarding a fraction F of the fragments
+    --alu-ops LIST      also measure fragment shaders doing each number of multiply-adds
+    --sweep-instances LIST
+                        also measure the color test with each instance count
+    --sweep-resolution LIST
+                        also measure the color test into framebuffers of each WxH size
+    --vertex-sweep LIST also measure the triangle rate drawing each number of
+                        sub-pixel triangles
+    --coverage-sweep LIST
+                        also measure fill with triangles of each size in pixels
+    --upload SIZE       also measure uploading a SIZExSIZE RGBA8 texture every frame
+    --mipmap SIZE       also measure generating the mipmaps of a SIZExSIZE texture
+    --uniforms MODE     also measure updating uniforms every frame, with calls or ubo
+    --uniform-count N   vec4 uniforms updated per frame (default: 16)
+    --indirect          also measure the color and depth test drawn by glDrawArraysIndirect
+    --multi-draw N      also measure submitting N empty draws, one call each and all in
+                        a single glMultiDrawArraysIndirect (GL 4.3)
+    --compute           also measure a compute shader writing an image (GL 4.3)
+    --image-store       also measure the fragment shader writing the pixels to an image
+                        of the color format, rather than to the color buffer (GL 4.3)
+    --ssbo MODE         also measure a compute shader streaming through a storage
+                        buffer: read, write or rmw for both (GL 4.3)
+    --ssbo-size MB      size of the storage buffer in MiB (default: 64)
+    --transform-feedback N
+                        also measure capturing the outputs of N vertices per frame
+                        with transform feedback, without rasterizing them
+    --readback N        also measure reading every frame back through N pixel buffers:
+                        1 to wait for each frame, 2 to map the previous one instead
+    --fence-latency     also measure how long glClientWaitSync blocks on a fence
+                        inserted after the draws of every frame (GL 3.2)
+    --finish-vs-flush   also measure how long glFinish blocks after the draws of every
+                        frame, next to glFlush followed by glClientWaitSync (GL 3.2)
+    --screenshot PATH   save the last frame of the last test as a PNG image
+    --pipeline-stats    also count the primitives and fragment shader invocations
+                        of the draws (GL 4.6 or ARB_pipeline_statistics_query)
+    --samples-passed    also count the samples of the depth rejected draws passing
+                        the depth test (desktop GL)
+    --histogram         print a histogram of the measured frame times of every test
+    --verify            check that the draws reach the pixels after the tests
+    --caps              print the limits and the video memory of the GL implementation
+                        at startup, which the JSON report always includes
+    --list-extensions   print the extensions of the context at startup
+    --tests LIST        main tests to run, in the given order, out of color,
+                        depth-reject and clear, or 'list' to print them (default: all)
+    --repeat N          run the whole suite N times and report the medians (default: 1)
+    --verbose           with --repeat, also print the results of every repeat
+    --vs PATH           vertex shader replacing the built-in full-screen triangle
+    --fs PATH           fragment shader replacing the built-in constant color
+    --format FORMAT     output format: text, json or csv (default: text)
+    --markdown-out PATH append the results as a Markdown table row to PATH
+    --frames-out PATH   write the time of every frame of every test to PATH as CSV
+    --save-baseline PATH
+                        save the results as JSON, to compare later runs against
+    --baseline PATH     compare the results with a baseline saved before
+    --threshold PCT     fail if a test got slower than the baseline by over PCT%
+    -h, --help          print this message
+
+Command-line options take precedence over the values in the config file.
+";
+
+static DEFAULT_CONFIG_PATH: &str = "gl-bench.toml";
+
+/// Size of a window or a render target, written as `WIDTHxHEIGHT`.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        let error = || format!("expected WIDTHxHEIGHT, got '{}'", s);
+        let mut parts = s.splitn(2, 'x');
+        let width = parts.next().and_then(|w| w.parse().ok()).ok_or_else(error)?;
+        let height = parts.next().and_then(|h| h.parse().ok()).ok_or_else(error)?;
+        Ok(Resolution { width, height })
+    }
+}
+
+impl TryFrom<String> for Resolution {
+    type Error = String;
+    fn try_from(s: String) -> Result<Self, String> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Primitive type used by the extra fill test, next to the
+/// full-screen triangles of the default ones.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Primitive {
+    Triangles,
+    /// A grid of points tiling the screen.
+    Points,
+    /// Horizontal lines stacked on top of each other.
+    Lines,
+}
+
+impl FromStr for Primitive {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "triangles" => Ok(Primitive::Triangles),
+            "points" => Ok(Primitive::Points),
+            "lines" => Ok(Primitive::Lines),
+            _ => Err(format!("unknown primitive '{}'", s)),
+        }
+    }
+}
+
+/// How the uniform update test uploads its values every frame.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UniformUpdate {
+    /// One `glUniform4f` call per value.
+    Calls,
+    /// A single `glBufferSubData` into a uniform buffer.
+    Ubo,
+}
+
+impl FromStr for UniformUpdate {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "calls" => Ok(UniformUpdate::Calls),
+            "ubo" => Ok(UniformUpdate::Ubo),
+            _ => Err(format!("unknown uniform update '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for UniformUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            UniformUpdate::Calls => "calls",
+            UniformUpdate::Ubo => "ubo",
+        })
+    }
+}
+
+/// How the streaming test uploads its vertices every frame.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StreamMode {
+    /// `glBufferData` with no data, then `glBufferSubData`.
+    Orphan,
+    /// `glMapBufferRange` invalidating the previous contents.
+    Map,
+    /// A coherent persistent mapping of `glBufferStorage`, of several
+    /// regions written in turn.
+    Persistent,
+}
+
+impl FromStr for StreamMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "orphan" => Ok(StreamMode::Orphan),
+            "map" => Ok(StreamMode::Map),
+            "persistent" => Ok(StreamMode::Persistent),
+            _ => Err(format!("unknown streaming mode '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for StreamMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            StreamMode::Orphan => "orphan",
+            StreamMode::Map => "map",
+            StreamMode::Persistent => "persistent",
+        })
+    }
+}
+
+/// How the storage buffer test accesses its buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub enum SsboAccess {
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "write")]
+    Write,
+    /// Every element is read, then written back.
+    #[serde(rename = "rmw")]
+    ReadWrite,
+}
+
+impl SsboAccess {
+    /// Bytes transferred per byte of the buffer.
+    pub fn passes(self) -> usize {
+        match self {
+            SsboAccess::Read | SsboAccess::Write => 1,
+            SsboAccess::ReadWrite => 2,
+        }
+    }
+}
+
+impl FromStr for SsboAccess {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "read" => Ok(SsboAccess::Read),
+            "write" => Ok(SsboAccess::Write),
+            "rmw" => Ok(SsboAccess::ReadWrite),
+            _ => Err(format!("unknown storage buffer access '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for SsboAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            SsboAccess::Read => "read",
+            SsboAccess::Write => "write",
+            SsboAccess::ReadWrite => "rmw",
+        })
+    }
+}
+
+/// Desktop GL context profile.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    Core,
+    /// Compatibility profile, keeping the deprecated functionality.
+    Compat,
+}
+
+impl FromStr for Profile {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "core" => Ok(Profile::Core),
+            "compat" => Ok(Profile::Compat),
+            _ => Err(format!("unknown profile '{}'", s)),
+        }
+    }
+}
+
+/// Texture filtering of the sampling tests.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+impl FromStr for Filter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "nearest" => Ok(Filter::Nearest),
+            "linear" => Ok(Filter::Linear),
+            _ => Err(format!("unknown filter '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Filter::Nearest => "nearest",
+            Filter::Linear => "linear",
+        })
+    }
+}
+
+/// Internal format of the color attachments and of the sampled texture.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorFormat {
+    Rgba8,
+    Rgba16f,
+    #[serde(rename = "r11f_g11f_b10f")]
+    R11fG11fB10f,
+}
+
+impl FromStr for ColorFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "rgba8" => Ok(ColorFormat::Rgba8),
+            "rgba16f" => Ok(ColorFormat::Rgba16f),
+            "r11f_g11f_b10f" => Ok(ColorFormat::R11fG11fB10f),
+            _ => Err(format!("unknown color format '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for ColorFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            ColorFormat::Rgba8 => "rgba8",
+            ColorFormat::Rgba16f => "rgba16f",
+            ColorFormat::R11fG11fB10f => "r11f_g11f_b10f",
+        })
+    }
+}
+
+/// Comparison of the depth test.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DepthFunc {
+    Never,
+    Less,
+    Equal,
+    Lequal,
+    Greater,
+    Notequal,
+    Gequal,
+    Always,
+}
+
+impl DepthFunc {
+    /// Whether the nearer fragments have the greater depth, as with
+    /// reverse-Z, rather than the smaller one.
+    pub fn is_reversed(self) -> bool {
+        self == DepthFunc::Greater || self == DepthFunc::Gequal
+    }
+}
+
+impl FromStr for DepthFunc {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "never" => Ok(DepthFunc::Never),
+            "less" => Ok(DepthFunc::Less),
+            "equal" => Ok(DepthFunc::Equal),
+            "lequal" => Ok(DepthFunc::Lequal),
+            "greater" => Ok(DepthFunc::Greater),
+            "notequal" => Ok(DepthFunc::Notequal),
+            "gequal" => Ok(DepthFunc::Gequal),
+            "always" => Ok(DepthFunc::Always),
+            _ => Err(format!("unknown depth function '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for DepthFunc {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DepthFunc::Never => "never",
+            DepthFunc::Less => "less",
+            DepthFunc::Equal => "equal",
+            DepthFunc::Lequal => "lequal",
+            DepthFunc::Greater => "greater",
+            DepthFunc::Notequal => "notequal",
+            DepthFunc::Gequal => "gequal",
+            DepthFunc::Always => "always",
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub num_queries: usize,
+    /// Leading frames excluded from the measurements.
+    pub warmup_frames: usize,
+    /// Trailing frames excluded from the measurements.
+    pub cooldown_frames: usize,
+    pub num_rejects: usize,
+    /// Instances drawn by the fill tests, each of them passing the depth test.
+    pub overdraw: usize,
+    pub clear_scissored: bool,
+    /// Scissor rectangle of the clears as X, Y, width and height,
+    /// or `None` for a quarter of the screen.
+    pub scissor: Option<[i32; 4]>,
+    /// Window size, or `None` for full-screen on the primary monitor.
+    pub windowed: Option<Resolution>,
+    /// Index of the full-screen monitor, or `None` for the primary one.
+    pub monitor: Option<usize>,
+    pub headless: bool,
+    /// Size of the render target in headless mode.
+    pub resolution: Resolution,
+    /// Size of the framebuffer object the tests render to, when it
+    /// differs from the window's.
+    pub fbo_resolution: Option<Resolution>,
+    pub gles: bool,
+    /// Profile of the desktop GL context, or `None` to leave it to the driver.
+    pub profile: Option<Profile>,
+    pub debug_gl: bool,
+    pub vsync: bool,
+    /// Samples per pixel of the window framebuffer, 0 or 1 to disable MSAA.
+    pub msaa: u16,
+    /// Percentiles of the full-screen time to report, from 0 to 100.
+    pub percentiles: Vec<f64>,
+    /// Wall-clock budget of every test in seconds, after which
+    /// no more frames are issued.
+    pub max_duration: Option<f64>,
+    pub outlier_filter: OutlierFilter,
+    pub timing: Timing,
+    pub primitive: Primitive,
+    pub point_size: f32,
+    pub line_width: f32,
+    /// Triangles covering the screen in the vertex-load test, if enabled.
+    pub tris_per_draw: Option<usize>,
+    pub attribs: bool,
+    /// How the streaming test uploads the triangle grid, if enabled.
+    pub stream: Option<StreamMode>,
+    pub blend: bool,
+    pub blend_layers: usize,
+    pub texture: bool,
+    pub texture_size: u32,
+    pub texture_filter: Filter,
+    /// Maximum anisotropy levels of the anisotropic filtering test,
+    /// which is skipped if empty.
+    pub aniso: Vec<u32>,
+    /// Face size of the cubemap sampled by the cubemap test, if enabled.
+    pub cubemap: Option<u32>,
+    /// Width, height and depth of the 3D texture sampled by the 3D
+    /// texture test, if enabled.
+    pub texture_3d: Option<u32>,
+    /// Layers of `texture_size` of the array texture test, if enabled.
+    pub texture_array: Option<u32>,
+    /// Internal format of the offscreen color attachments and of the
+    /// sampled texture. The window's own framebuffer is always RGBA8.
+    pub color_format: ColorFormat,
+    /// Whether the render target is sRGB-capable, and the sRGB test enabled.
+    pub srgb: bool,
+    pub hdr: bool,
+    pub wireframe: bool,
+    pub cull: bool,
+    pub depth_only: bool,
+    pub render_to_texture: bool,
+    /// Number of color attachments of the MRT test, 0 to skip it.
+    pub mrt: usize,
+    pub stencil: bool,
+    pub stencil_reject: f32,
+    /// Fraction of the fragments killed by the discard test, if enabled.
+    pub discard: Option<f32>,
+    /// Multiply-adds per fragment of each ALU test.
+    pub alu_ops: Vec<usize>,
+    /// Size of the texture uploaded by the upload test, if enabled.
+    pub upload: Option<u32>,
+    /// Size of the texture the mipmap test generates the levels of, if enabled.
+    pub mipmap: Option<u32>,
+    /// How the uniform update test uploads its values, if enabled.
+    pub uniforms: Option<UniformUpdate>,
+    pub uniform_count: usize,
+    pub indirect: bool,
+    /// Draws submitted per frame by the multi-draw test, if enabled.
+    pub multi_draw: Option<usize>,
+    pub compute: bool,
+    pub image_store: bool,
+    /// Access pattern of the storage buffer test, if enabled.
+    pub ssbo: Option<SsboAccess>,
+    /// Size of the storage buffer, in MiB.
+    pub ssbo_size: usize,
+    /// Vertices captured per frame by the transform feedback test, if enabled.
+    pub transform_feedback: Option<usize>,
+    /// Pixel buffers the readback test cycles through, if enabled.
+    pub readback: Option<usize>,
+    pub fence_latency: bool,
+    pub finish_vs_flush: bool,
+    /// Instance counts of the overdraw sweep.
+    pub sweep_instances: Vec<usize>,
+    /// Framebuffer sizes of the resolution sweep.
+    pub sweep_resolution: Vec<Resolution>,
+    /// Triangle counts of the vertex throughput sweep.
+    pub sweep_vertices: Vec<usize>,
+    /// Leg lengths in pixels of the triangles of the coverage sweep.
+    pub sweep_coverage: Vec<u32>,
+    /// Clear color of the "color and depth" test.
+    pub clear_color: [f32; 4],
+    /// Depth the tests clear to.
+    pub clear_depth: f32,
+    /// Comparison of the depth test. The reversed ones also reverse
+    /// the depth range, so that the nearer instances still pass it.
+    pub depth_func: DepthFunc,
+    pub pipeline_stats: bool,
+    pub samples_passed: bool,
+    /// Whether the results of every test include a histogram
+    /// of its measured frame times.
+    pub histogram: bool,
+    pub verify: bool,
+    /// Whether the limits and the video memory of the implementation
+    /// are printed at startup.
+    pub caps: bool,
+    pub list_extensions: bool,
+    pub screenshot: Option<PathBuf>,
+    /// Shader files replacing the built-in sources of the fill tests.
+    pub vs: Option<PathBuf>,
+    pub fs: Option<PathBuf>,
+    /// Names of the main tests to run, in order, or none for all of them.
+    pub tests: Vec<String>,
+    /// Number of times the whole suite is run.
+    pub repeat: usize,
+    pub verbose: bool,
+    pub format: Format,
+    /// Markdown file accumulating the results of every run.
+    pub markdown_out: Option<PathBuf>,
+    /// CSV file receiving the time of every frame, warmup included.
+    pub frames_out: Option<PathBuf>,
+    pub save_baseline: Option<PathBuf>,
+    pub baseline: Option<PathBuf>,
+    /// Slowdown relative to the baseline, in percent, that fails the run.
+    pub threshold: Option<f64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            num_queries: 200,
+            warmup_frames: 40,
+            cooldown_frames: 0,
+            num_rejects: 20,
+            overdraw: 1,
+            clear_scissored: false,
+            scissor: None,
+            windowed: None,
+            monitor: None,
+            headless: false,
+            resolution: Resolution { width: 1920, height: 1080 },
+            fbo_resolution: None,
+            gles: false,
+            profile: None,
+            debug_gl: false,
+            vsync: false,
+            msaa: 0,
+            percentiles: vec![50.0, 95.0, 99.0],
+            max_duration: None,
+            outlier_filter: OutlierFilter::Warmup,
+            timing: Timing::Elapsed,
+            primitive: Primitive::Triangles,
+            point_size: 16.0,
+            line_width: 1.0,
+            tris_per_draw: None,
+            attribs: false,
+            stream: None,
+            blend: false,
+            blend_layers: 4,
+            texture: false,
+            texture_size: 1024,
+            texture_filter: Filter::Linear,
+            aniso: Vec::new(),
+            cubemap: None,
+            texture_3d: None,
+            texture_array: None,
+            color_format: ColorFormat::Rgba8,
+            srgb: false,
+            hdr: false,
+            wireframe: false,
+            cull: false,
+            depth_only: false,
+            render_to_texture: false,
+            mrt: 0,
+            stencil: false,
+            stencil_reject: 0.5,
+            discard: None,
+            alu_ops: Vec::new(),
+            upload: None,
+            mipmap: None,
+            uniforms: None,
+            uniform_count: 16,
+            indirect: false,
+            multi_draw: None,
+            compute: false,
+            image_store: false,
+            ssbo: None,
+            ssbo_size: 64,
+            transform_feedback: None,
+            readback: None,
+            fence_latency: false,
+            finish_vs_flush: false,
+            sweep_instances: Vec::new(),
+            sweep_resolution: Vec::new(),
+            sweep_vertices: Vec::new(),
+            sweep_coverage: Vec::new(),
+            clear_color: [0.3, 0.3, 0.3, 1.0],
+            clear_depth: 1.0,
+            depth_func: DepthFunc::Less,
+            pipeline_stats: false,
+            samples_passed: false,
+            histogram: false,
+            verify: false,
+            caps: false,
+            list_extensions: false,
+            screenshot: None,
+            vs: None,
+            fs: None,
+            tests: Vec::new(),
+            repeat: 1,
+            verbose: false,
+            format: Format::Text,
+            markdown_out: None,
+            frames_out: None,
+            save_baseline: None,
+            baseline: None,
+            threshold: None,
+        }
+    }
+}
+
+/// Builds a `Config` from the defaults, for programs embedding the
+/// benchmark: `Config::builder().num_queries(500).build()`.
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+/// Generates a setter on the builder for every listed field.
+macro_rules! setters {
+    ($($field:ident: $ty:ty,)*) => {
+        impl ConfigBuilder {
+            $(
+                pub fn $field(mut self, $field: $ty) -> Self {
+                    self.config.$field = $field;
+                    self
+                }
+            )*
+        }
+    };
+}
+
+setters! {
+    num_queries: usize,
+    warmup_frames: usize,
+    cooldown_frames: usize,
+    num_rejects: usize,
+    overdraw: usize,
+    clear_scissored: bool,
+    scissor: Option<[i32; 4]>,
+    windowed: Option<Resolution>,
+    monitor: Option<usize>,
+    headless: bool,
+    resolution: Resolution,
+    fbo_resolution: Option<Resolution>,
+    gles: bool,
+    profile: Option<Profile>,
+    debug_gl: bool,
+    vsync: bool,
+    msaa: u16,
+    percentiles: Vec<f64>,
+    max_duration: Option<f64>,
+    outlier_filter: OutlierFilter,
+    timing: Timing,
+    primitive: Primitive,
+    point_size: f32,
+    line_width: f32,
+    tris_per_draw: Option<usize>,
+    attribs: bool,
+    stream: Option<StreamMode>,
+    blend: bool,
+    blend_layers: usize,
+    texture: bool,
+    texture_size: u32,
+    texture_filter: Filter,
+    aniso: Vec<u32>,
+    cubemap: Option<u32>,
+    texture_3d: Option<u32>,
+    texture_array: Option<u32>,
+    color_format: ColorFormat,
+    srgb: bool,
+    hdr: bool,
+    wireframe: bool,
+    cull: bool,
+    depth_only: bool,
+    render_to_texture: bool,
+    mrt: usize,
+    stencil: bool,
+    stencil_reject: f32,
+    discard: Option<f32>,
+    alu_ops: Vec<usize>,
+    upload: Option<u32>,
+    mipmap: Option<u32>,
+    uniforms: Option<UniformUpdate>,
+    uniform_count: usize,
+    indirect: bool,
+    multi_draw: Option<usize>,
+    compute: bool,
+    image_store: bool,
+    ssbo: Option<SsboAccess>,
+    ssbo_size: usize,
+    transform_feedback: Option<usize>,
+    readback: Option<usize>,
+    fence_latency: bool,
+    finish_vs_flush: bool,
+    sweep_instances: Vec<usize>,
+    sweep_resolution: Vec<Resolution>,
+    sweep_vertices: Vec<usize>,
+    sweep_coverage: Vec<u32>,
+    clear_color: [f32; 4],
+    clear_depth: f32,
+    depth_func: DepthFunc,
+    pipeline_stats: bool,
+    samples_passed: bool,
+    histogram: bool,
+    verify: bool,
+    caps: bool,
+    list_extensions: bool,
+    screenshot: Option<PathBuf>,
+    vs: Option<PathBuf>,
+    fs: Option<PathBuf>,
+    tests: Vec<String>,
+    repeat: usize,
+    verbose: bool,
+    format: Format,
+    markdown_out: Option<PathBuf>,
+    frames_out: Option<PathBuf>,
+    save_baseline: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    threshold: Option<f64>,
+}
+
+impl ConfigBuilder {
+    pub fn build(self) -> Config {
+        self.config
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for '{}'", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, flag))
+}
+
+fn parse_list<T: FromStr>(flag: &str, value: Option<String>) -> Result<Vec<T>, String> {
+    let value = value.ok_or_else(|| format!("missing value for '{}'", flag))?;
+    value
+        .split(',')
+        .map(|item| item.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("invalid value '{}' for '{}'", value, flag))
+}
+
+impl Config {
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder { config: Config::default() }
+    }
+
+    /// Indices of the frames that are measured out of `num_frames`.
+    pub fn measured_range(&self, num_frames: usize) -> Range<usize> {
+        self.warmup_frames .. num_frames - self.cooldown_frames
+    }
+
+    /// Whether the results of every test run are printed as they come,
+    /// rather than just the summary of the repeats.
+    pub fn prints_runs(&self) -> bool {
+        self.format == Format::Text && (self.repeat == 1 || self.verbose)
+    }
+
+    /// Reads the configuration file at `path`. A missing file at the
+    /// default location is not an error and yields the default values.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound && path == DEFAULT_CONFIG_PATH => {
+                return Ok(Config::default())
+            }
+            Err(e) => return Err(format!("failed to read '{}': {}", path, e)),
+        };
+        toml::from_str(&text).map_err(|e| format!("failed to parse '{}': {}", path, e))
+    }
+
+    /// Builds a configuration from the command-line arguments,
+    /// not including the program name, or `None` if they ask
+    /// for the usage with `--help`.
+    pub fn from_args<I: Iterator<Item = String>>(args: I) -> Result<Option<Self>, String> {
+        let args = args.collect::<Vec<_>>();
+        let path = match args.iter().position(|arg| arg == "--config") {
+            Some(i) => args
+                .get(i + 1)
+                .ok_or_else(|| "missing value for '--config'".to_string())?
+                .as_str(),
+            None => DEFAULT_CONFIG_PATH,
+        };
+        let mut config = Config::from_file(path)?;
+
+        let mut args = args.iter().cloned();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    args.next();
+                }
+                "--queries" => config.num_queries = parse_value(&arg, args.next())?,
+                "--warmup" => config.warmup_frames = parse_value(&arg, args.next())?,
+                "--cooldown" => config.cooldown_frames = parse_value(&arg, args.next())?,
+                "--rejects" => config.num_rejects = parse_value(&arg, args.next())?,
+                "--overdraw" => config.overdraw = parse_value(&arg, args.next())?,
+                "--clear-color" => {
+                    let color = parse_list::<f32>(&arg, args.next())?;
+                    if color.len() != 4 {
+                        return Err(format!("expected R,G,B,A for '--clear-color', got {} values",
+                            color.len()));
+                    }
+                    config.clear_color = [color[0], color[1], color[2], color[3]];
+                }
+                "--clear-depth" => config.clear_depth = parse_value(&arg, args.next())?,
+                "--depth-func" => config.depth_func = parse_value(&arg, args.next())?,
+                "--clear-scissored" => config.clear_scissored = true,
+                "--scissor" => {
+                    let rect = parse_list::<i32>(&arg, args.next())?;
+                    if rect.len() != 4 {
+                        return Err(format!("expected X,Y,W,H for '--scissor', got {} values", rect.len()));
+                    }
+                    config.scissor = Some([rect[0], rect[1], rect[2], rect[3]]);
+                    config.clear_scissored = true;
+                }
+                "--windowed" => config.windowed = Some(parse_value(&arg, args.next())?),
+                "--monitor" => config.monitor = Some(parse_value(&arg, args.next())?),
+                "--headless" => config.headless = true,
+                "--resolution" => config.resolution = parse_value(&arg, args.next())?,
+                "--fbo-resolution" => config.fbo_resolution = Some(parse_value(&arg, args.next())?),
+                "--gles" => config.gles = true,
+                "--profile" => config.profile = Some(parse_value(&arg, args.next())?),
+                "--debug-gl" => config.debug_gl = true,
+                "--vsync" => config.vsync = true,
+                "--percentiles" => config.percentiles = parse_list(&arg, args.next())?,
+                "--max-duration" => config.max_duration = Some(parse_value(&arg, args.next())?),
+                "--outlier-filter" => config.outlier_filter = parse_value(&arg, args.next())?,
+                "--timing" => config.timing = parse_value(&arg, args.next())?,
+                "--primitive" => config.primitive = parse_value(&arg, args.next())?,
+                "--point-size" => config.point_size = parse_value(&arg, args.next())?,
+                "--line-width" => config.line_width = parse_value(&arg, args.next())?,
+                "--tris-per-draw" => config.tris_per_draw = Some(parse_value(&arg, args.next())?),
+                "--attribs" => config.attribs = true,
+                "--stream" => config.stream = Some(parse_value(&arg, args.next())?),
+                "--blend" => config.blend = true,
+                "--blend-layers" => config.blend_layers = parse_value(&arg, args.next())?,
+                "--texture" => config.texture = true,
+                "--texture-size" => config.texture_size = parse_value(&arg, args.next())?,
+                "--filter" => config.texture_filter = parse_value(&arg, args.next())?,
+                "--aniso" => config.aniso = parse_list(&arg, args.next())?,
+                "--cubemap" => config.cubemap = Some(parse_value(&arg, args.next())?),
+                "--texture-3d" => config.texture_3d = Some(parse_value(&arg, args.next())?),
+                "--texture-array" => config.texture_array = Some(parse_value(&arg, args.next())?),
+                "--color-format" => config.color_format = parse_value(&arg, args.next())?,
+                "--srgb" => config.srgb = true,
+                "--hdr" => config.hdr = true,
+                "--wireframe" => config.wireframe = true,
+                "--cull" => config.cull = true,
+                "--depth-only" => config.depth_only = true,
+                "--render-to-texture" => config.render_to_texture = true,
+                "--mrt" => config.mrt = parse_value(&arg, args.next())?,
+                "--msaa" => config.msaa = parse_value(&arg, args.next())?,
+                "--stencil" => config.stencil = true,
+                "--stencil-reject" => config.stencil_reject = parse_value(&arg, args.next())?,
+                "--discard" => config.discard = Some(parse_value(&arg, args.next())?),
+                "--alu-ops" => config.alu_ops = parse_list(&arg, args.next())?,
+                "--upload" => config.upload = Some(parse_value(&arg, args.next())?),
+                "--mipmap" => config.mipmap = Some(parse_value(&arg, args.next())?),
+                "--uniforms" => config.uniforms = Some(parse_value(&arg, args.next())?),
+                "--uniform-count" => config.uniform_count = parse_value(&arg, args.next())?,
+                "--indirect" => config.indirect = true,
+                "--multi-draw" => config.multi_draw = Some(parse_value(&arg, args.next())?),
+                "--compute" => config.compute = true,
+                "--image-store" => config.image_store = true,
+                "--ssbo" => config.ssbo = Some(parse_value(&arg, args.next())?),
+                "--ssbo-size" => config.ssbo_size = parse_value(&arg, args.next())?,
+                "--transform-feedback" => {
+                    config.transform_feedback = Some(parse_value(&arg, args.next())?)
+                }
+                "--readback" => config.readback = Some(parse_value(&arg, args.next())?),
+                "--fence-latency" => config.fence_latency = true,
+                "--finish-vs-flush" => config.finish_vs_flush = true,
+                "--sweep-instances" => config.sweep_instances = parse_list(&arg, args.next())?,
+                "--sweep-resolution" => config.sweep_resolution = parse_list(&arg, args.next())?,
+                "--vertex-sweep" => config.sweep_vertices = parse_list(&arg, args.next())?,
+                "--coverage-sweep" => config.sweep_coverage = parse_list(&arg, args.next())?,
+                "--pipeline-stats" => config.pipeline_stats = true,
+                "--samples-passed" => config.samples_passed = true,
+                "--histogram" => config.histogram = true,
+                "--verify" => config.verify = true,
+                "--caps" => config.caps = true,
+                "--list-extensions" => config.list_extensions = true,
+                "--screenshot" => config.screenshot = Some(parse_value(&arg, args.next())?),
+                "--vs" => config.vs = Some(parse_value(&arg, args.next())?),
+                "--fs" => config.fs = Some(parse_value(&arg, args.next())?),
+                "--tests" => config.tests = parse_list(&arg, args.next())?,
+                "--repeat" => config.repeat = parse_value(&arg, args.next())?,
+                "--verbose" => config.verbose = true,
+                "--format" => config.format = parse_value(&arg, args.next())?,
+                "--markdown-out" => config.markdown_out = Some(parse_value(&arg, args.next())?),
+                "--frames-out" => config.frames_out = Some(parse_value(&arg, args.next())?),
+                "--save-baseline" => config.save_baseline = Some(parse_value(&arg, args.next())?),
+                "--baseline" => config.baseline = Some(parse_value(&arg, args.next())?),
+                "--threshold" => config.threshold = Some(parse_value(&arg, args.next())?),
+                "-h" | "--help" => return Ok(None),
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        config.validate()?;
+        Ok(Some(config))
+    }
+
+    /// Checks the parameters for values the tests can't run with,
+    /// naming the offending one.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_queries == 0 {
+            return Err("the number of queries must be at least 1".to_string());
+        }
+        if self.num_rejects == 0 {
+            return Err("the number of rejected instances must be at least 1".to_string());
+        }
+        if self.warmup_frames + self.cooldown_frames >= self.num_queries {
+            return Err(format!(
+                "{} warmup and {} cooldown frames leave nothing to measure out of {} queries",
+                self.warmup_frames, self.cooldown_frames, self.num_queries,
+            ));
+        }
+        if self.upload == Some(0) {
+            return Err("the upload texture size must be at least 1".to_string());
+        }
+        if self.cubemap == Some(0) || self.texture_3d == Some(0) {
+            return Err("the texture sizes must be at least 1".to_string());
+        }
+        if self.texture_array == Some(0) {
+            return Err("the texture array needs at least 1 layer".to_string());
+        }
+        if self.aniso.contains(&0) {
+            return Err("the anisotropy levels must be at least 1".to_string());
+        }
+        if self.mipmap.is_some_and(|size| size < 2) {
+            return Err("the mipmapped texture needs a size of at least 2".to_string());
+        }
+        if self.sweep_coverage.contains(&0) {
+            return Err("the triangle sizes must be at least 1 pixel".to_string());
+        }
+        if !self.point_size.is_finite() || self.point_size <= 0.0 {
+            return Err(format!("point size {} is not a positive number of pixels", self.point_size));
+        }
+        if self.multi_draw == Some(0) {
+            return Err("the multi-draw test needs at least 1 draw".to_string());
+        }
+        if self.ssbo_size == 0 {
+            return Err("the storage buffer size must be at least 1 MiB".to_string());
+        }
+        if self.transform_feedback == Some(0) {
+            return Err("the transform feedback test needs at least 1 vertex".to_string());
+        }
+        if let Some(buffers) = self.readback {
+            if buffers != 1 && buffers != 2 {
+                return Err(format!("the readback test uses 1 or 2 pixel buffers, not {}", buffers));
+            }
+        }
+        if self.uniform_count == 0 {
+            return Err("the uniform count must be at least 1".to_string());
+        }
+        if self.tris_per_draw == Some(0) || self.sweep_vertices.contains(&0) {
+            return Err("the triangle count per draw must be at least 1".to_string());
+        }
+        if self.overdraw == 0 || self.sweep_instances.contains(&0) {
+            return Err("the instance counts must be at least 1".to_string());
+        }
+        if let Some(duration) = self.max_duration.filter(|&d| !d.is_finite() || d <= 0.0) {
+            return Err(format!("maximum duration {} is not a positive number of seconds", duration));
+        }
+        if self.repeat == 0 {
+            return Err("the suite must be repeated at least once".to_string());
+        }
+        if let Some([_, _, width, height]) = self.scissor.filter(|r| r[2] < 0 || r[3] < 0) {
+            return Err(format!("scissor size {}x{} is negative", width, height));
+        }
+        if self.msaa > 1 && !self.msaa.is_power_of_two() {
+            return Err(format!("{} MSAA samples is not a power of two", self.msaa));
+        }
+        if !(0.0 ..= 1.0).contains(&self.stencil_reject) {
+            return Err(format!("stencil reject fraction {} is outside of the 0-1 range",
+                self.stencil_reject));
+        }
+        if !(0.0 ..= 1.0).contains(&self.clear_depth) {
+            return Err(format!("clear depth {} is outside of the 0-1 range", self.clear_depth));
+        }
+        if let Some(fraction) = self.discard.filter(|f| !(0.0 ..= 1.0).contains(f)) {
+            return Err(format!("discard fraction {} is outside of the 0-1 range", fraction));
+        }
+        if let Some(p) = self.percentiles.iter().find(|&&p| !(0.0 ..= 100.0).contains(&p)) {
+            return Err(format!("percentile {} is outside of the 0-100 range", p));
+        }
+        if self.headless && (self.windowed.is_some() || self.monitor.is_some()) {
+            return Err("'--headless' can't be combined with a window or a monitor".to_string());
+        }
+        if self.gles && self.profile.is_some() {
+            return Err("'--profile' only applies to desktop GL contexts".to_string());
+        }
+        if self.srgb && self.color_format != ColorFormat::Rgba8 {
+            return Err("'--srgb' only applies to the rgba8 color format".to_string());
+        }
+        if self.threshold.is_some() && self.baseline.is_none() {
+            return Err("'--threshold' requires a '--baseline' to compare with".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measured_range_skips_warmup_and_cooldown() {
+        let configs = [
+            Config::default(),
+            Config::builder().cooldown_frames(25).build(),
+            Config::builder().num_queries(100).warmup_frames(60).cooldown_frames(39).build(),
+        ];
+        for config in &configs {
+            let range = config.measured_range(config.num_queries);
+            assert_eq!(range.start, config.warmup_frames);
+            assert_eq!(range.len(),
+                config.num_queries - config.warmup_frames - config.cooldown_frames);
+        }
+    }
+
+    #[test]
+    fn from_args_returns_no_config_for_help() {
+        let args = ["--queries", "100", "--help"].iter().map(|arg| arg.to_string());
+        assert!(matches!(Config::from_args(args), Ok(None)));
+    }
+
+    #[test]
+    fn validate_accepts_the_defaults() {
+        assert_eq!(Config::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_accepts_a_long_warmup() {
+        let config = Config::builder().num_queries(200).warmup_frames(150).build();
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_every_invalid_parameter() {
+        let default = Config::default;
+        let invalid = [
+            Config { num_queries: 0, ..default() },
+            Config { num_rejects: 0, ..default() },
+            Config { num_queries: 100, warmup_frames: 60, cooldown_frames: 40, ..default() },
+            Config { upload: Some(0), ..default() },
+            Config { cubemap: Some(0), ..default() },
+            Config { texture_3d: Some(0), ..default() },
+            Config { texture_array: Some(0), ..default() },
+            Config { aniso: vec![1, 0], ..default() },
+            Config { mipmap: Some(1), ..default() },
+            Config { sweep_coverage: vec![0], ..default() },
+            Config { point_size: 0.0, ..default() },
+            Config { point_size: f32::NAN, ..default() },
+            Config { multi_draw: Some(0), ..default() },
+            Config { ssbo_size: 0, ..default() },
+            Config { transform_feedback: Some(0), ..default() },
+            Config { readback: Some(3), ..default() },
+            Config { uniform_count: 0, ..default() },
+            Config { tris_per_draw: Some(0), ..default() },
+            Config { sweep_vertices: vec![0], ..default() },
+            Config { overdraw: 0, ..default() },
+            Config { sweep_instances: vec![0], ..default() },
+            Config { max_duration: Some(0.0), ..default() },
+            Config { repeat: 0, ..default() },
+            Config { scissor: Some([0, 0, -1, 10]), ..default() },
+            Config { msaa: 3, ..default() },
+            Config { stencil_reject: 1.5, ..default() },
+            Config { clear_depth: -0.5, ..default() },
+            Config { discard: Some(2.0), ..default() },
+            Config { percentiles: vec![50.0, 101.0], ..default() },
+            Config { headless: true, monitor: Some(0), ..default() },
+            Config { gles: true, profile: Some(Profile::Core), ..default() },
+            Config { srgb: true, color_format: ColorFormat::Rgba16f, ..default() },
+            Config { threshold: Some(5.0), ..default() },
+        ];
+        for (i, config) in invalid.iter().enumerate() {
+            assert!(config.validate().is_err(), "config {} passed validation", i);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! The GL context the tests render with, on screen or off it
+
+use std::cell::{Cell, RefCell};
+
+use gl;
+use glutin::{self, GlContext};
+
+use config::{Config, Profile};
+
+/// What the tests present their frames to.
+pub trait Surface {
+    /// Size of the default framebuffer, in physical pixels, which
+    /// already accounts for the hiDPI factor.
+    fn size(&self) -> (u32, u32);
+
+    fn hidpi_factor(&self) -> f32 {
+        1.0
+    }
+
+    /// Whether there is no default framebuffer to render to, in which
+    /// case the tests render to a framebuffer object of `size` instead.
+    fn is_headless(&self) -> bool {
+        false
+    }
+
+    /// Handles the pending events, returning whether the tests
+    /// should stop.
+    fn poll_events(&self) -> bool {
+        false
+    }
+
+    /// Applies a pending resize, returning whether there was one.
+    fn apply_resize(&self) -> bool {
+        false
+    }
+
+    /// Presents the frame.
+    fn swap_buffers(&self) -> Result<(), String> {
+        unsafe { gl::Flush() };
+        Ok(())
+    }
+}
+
+/// A context created by the embedding program, current on the calling
+/// thread. The tests render offscreen, to a framebuffer object of the
+/// given size, leaving the program's framebuffers alone.
+pub struct ExistingContext {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Surface for ExistingContext {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn is_headless(&self) -> bool {
+        true
+    }
+}
+
+/// The window, or the headless context, of the `gl-bench` binary.
+pub enum Context {
+    /// A window rendering to its default framebuffer. Boxed, as
+    /// it's much larger than the headless context.
+    Window {
+        gl_window: Box<glutin::GlWindow>,
+        events_loop: RefCell<glutin::EventsLoop>,
+        /// Latest size the window was resized to, not applied yet.
+        resized: Cell<Option<(u32, u32)>>,
+    },
+    /// An offscreen context, for machines without a display. The tests
+    /// render to a framebuffer object of the given size instead.
+    Headless {
+        context: glutin::HeadlessContext,
+        width: u32,
+        height: u32,
+    },
+}
+
+fn select_monitor(
+    events_loop: &glutin::EventsLoop, index: Option<usize>,
+) -> Result<glutin::MonitorId, String> {
+    let index = match index {
+        Some(index) => index,
+        None => return Ok(events_loop.get_primary_monitor()),
+    };
+    if let Some(monitor) = events_loop.get_available_monitors().nth(index) {
+        return Ok(monitor);
+    }
+    let mut message = format!("there is no monitor {}, available monitors are:", index);
+    for (i, monitor) in events_loop.get_available_monitors().enumerate() {
+        let (width, height) = monitor.get_dimensions();
+        message += &format!("\n\t{}: {} ({}x{})",
+            i, monitor.get_name().unwrap_or_default(), width, height);
+    }
+    Err(message)
+}
+
+fn gl_request(config: &Config) -> glutin::GlRequest {
+    if config.gles {
+        glutin::GlRequest::Specific(glutin::Api::OpenGlEs, (3, 0))
+    } else {
+        glutin::GlRequest::Latest
+    }
+}
+
+fn gl_profile(config: &Config) -> Option<glutin::GlProfile> {
+    match config.profile? {
+        Profile::Core => Some(glutin::GlProfile::Core),
+        Profile::Compat => Some(glutin::GlProfile::Compatibility),
+    }
+}
+
+impl Context {
+    /// Creates the window or the headless context the configuration
+    /// asks for, and makes it current.
+    pub fn new(config: &Config) -> Result<Self, String> {
+        if cfg!(target_os = "macos") && config.profile == Some(Profile::Compat) {
+            eprintln!("warning: macOS only provides GL 3.2 and later with the core profile, \
+                a compatibility context is limited to GL 2.1");
+        }
+        let context = if config.headless {
+            let size = config.resolution;
+            let mut builder = glutin::HeadlessRendererBuilder::new(size.width, size.height)
+                .with_gl(gl_request(config))
+                .with_gl_debug_flag(config.debug_gl);
+            if let Some(profile) = gl_profile(config) {
+                builder = builder.with_gl_profile(profile);
+            }
+            let context = builder
+                .build()
+                .map_err(|e| format!("failed to create the headless context: {}", e))?;
+            Context::Headless { context, width: size.width, height: size.height }
+        } else {
+            let events_loop = glutin::EventsLoop::new();
+            let window = glutin::WindowBuilder::new()
+                .with_title("GL fill-rate benchmark");
+            let window = match config.windowed {
+                Some(size) => window.with_dimensions(size.width, size.height),
+                None => window.with_fullscreen(Some(select_monitor(&events_loop, config.monitor)?)),
+            };
+            let mut context = glutin::ContextBuilder::new()
+                .with_gl(gl_request(config))
+                .with_gl_debug_flag(config.debug_gl)
+                .with_vsync(config.vsync)
+                .with_srgb(config.srgb)
+                .with_depth_buffer(24);
+            if let Some(profile) = gl_profile(config) {
+                context = context.with_gl_profile(profile);
+            }
+            if config.msaa > 1 {
+                context = context.with_multisampling(config.msaa);
+            }
+            if config.stencil {
+                context = context.with_stencil_buffer(8);
+            }
+            let gl_window = glutin::GlWindow::new(window, context, &events_loop)
+                .map_err(|e| format!("failed to create the window: {}", e))?;
+            Context::Window {
+                gl_window: Box::new(gl_window),
+                events_loop: RefCell::new(events_loop),
+                resized: Cell::new(None),
+            }
+        };
+
+        let result = unsafe {
+            match context {
+                Context::Window { ref gl_window, .. } => gl_window.make_current(),
+                Context::Headless { ref context, .. } => context.make_current(),
+            }
+        };
+        result.map_err(|e| format!("failed to make the context current: {:?}", e))?;
+        Ok(context)
+    }
+
+    pub fn get_proc_address(&self, symbol: &str) -> *const () {
+        match *self {
+            Context::Window { ref gl_window, .. } => gl_window.get_proc_address(symbol),
+            Context::Headless { ref context, .. } => context.get_proc_address(symbol),
+        }
+    }
+}
+
+impl Surface for Context {
+    /// Size of the render target, in physical pixels.
+    fn size(&self) -> (u32, u32) {
+        match *self {
+            Context::Window { ref gl_window, .. } => {
+                let (width, height) = gl_window.get_inner_size().unwrap();
+                // despite its documentation, winit reports the size in
+                // points on macOS, rather than in framebuffer pixels
+                if cfg!(target_os = "macos") {
+                    let hidpi = gl_window.hidpi_factor();
+                    ((width as f32 * hidpi).round() as u32, (height as f32 * hidpi).round() as u32)
+                } else {
+                    (width, height)
+                }
+            }
+            Context::Headless { width, height, .. } => (width, height),
+        }
+    }
+
+    fn hidpi_factor(&self) -> f32 {
+        match *self {
+            Context::Window { ref gl_window, .. } => gl_window.hidpi_factor(),
+            Context::Headless { .. } => 1.0,
+        }
+    }
+
+    fn is_headless(&self) -> bool {
+        match *self {
+            Context::Window { .. } => false,
+            Context::Headless { .. } => true,
+        }
+    }
+
+    /// Handles the pending window events, returning whether the window
+    /// was closed or Escape was pressed. Resizes are only recorded, to be
+    /// applied between tests by `apply_resize`.
+    fn poll_events(&self) -> bool {
+        let (events_loop, resized) = match *self {
+            Context::Window { ref events_loop, ref resized, .. } => (events_loop, resized),
+            Context::Headless { .. } => return false,
+        };
+        let mut close = false;
+        events_loop.borrow_mut().poll_events(|event| {
+            if let glutin::Event::WindowEvent { event, .. } = event {
+                match event {
+                    glutin::WindowEvent::Closed => close = true,
+                    glutin::WindowEvent::Resized(width, height) => resized.set(Some((width, height))),
+                    glutin::WindowEvent::KeyboardInput {
+                        input: glutin::KeyboardInput {
+                            state: glutin::ElementState::Pressed,
+                            virtual_keycode: Some(glutin::VirtualKeyCode::Escape),
+                            ..
+                        },
+                        ..
+                    } => close = true,
+                    _ => {}
+                }
+            }
+        });
+        close
+    }
+
+    /// Resizes the context to the latest size of the window, returning
+    /// whether it changed since the last call. Full-screen windows keep
+    /// the size of their monitor, so only windowed runs ever get resized.
+    fn apply_resize(&self) -> bool {
+        match *self {
+            Context::Window { ref gl_window, ref resized, .. } => match resized.take() {
+                Some((width, height)) => {
+                    gl_window.resize(width, height);
+                    true
+                }
+                None => false,
+            },
+            Context::Headless { .. } => false,
+        }
+    }
+
+    /// Presents the frame. Offscreen frames are only flushed, as
+    /// there is nothing to present them to.
+    fn swap_buffers(&self) -> Result<(), String> {
+        match *self {
+            Context::Window { ref gl_window, .. } => gl_window
+                .swap_buffers()
+                .map_err(|e| format!("failed to swap buffers: {:?}", e)),
+            Context::Headless { .. } => {
+                unsafe { gl::Flush() };
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Driver messages reported through `KHR_debug`
+
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::{ptr, slice};
+
+use gl;
+use gl::types::*;
+
+fn severity_name(severity: GLenum) -> &'static str {
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH => "high",
+        gl::DEBUG_SEVERITY_MEDIUM => "medium",
+        gl::DEBUG_SEVERITY_LOW => "low",
+        gl::DEBUG_SEVERITY_NOTIFICATION => "notification",
+        _ => "unknown",
+    }
+}
+
+fn type_name(ty: GLenum) -> &'static str {
+    match ty {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        _ => "other",
+    }
+}
+
+extern "system" fn print_message(
+    _source: GLenum,
+    ty: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    message: *const GLchar,
+    _user_param: *mut c_void,
+) {
+    // a negative length means the message is null-terminated
+    let message = unsafe {
+        if length < 0 {
+            CStr::from_ptr(message).to_bytes()
+        } else {
+            slice::from_raw_parts(message as *const u8, length as usize)
+        }
+    };
+    eprintln!("gl [{}] {} {}: {}",
+        severity_name(severity), type_name(ty), id, String::from_utf8_lossy(message));
+}
+
+/// Names a GL object in debuggers and in the driver's messages.
+pub fn label(identifier: GLenum, name: GLuint, label: &str) {
+    unsafe {
+        gl::ObjectLabel(identifier, name, label.len() as _, label.as_ptr() as *const GLchar);
+    }
+}
+
+/// Prints every message of the driver to stderr, as soon as the call
+/// raising it is made. Requires GL 4.3 or `KHR_debug`.
+pub fn install_callback() {
+    unsafe {
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl::DebugMessageCallback(print_message, ptr::null());
+    }
+}
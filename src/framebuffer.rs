@@ -0,0 +1,87 @@
+//! Offscreen render targets
+
+use gl;
+use gl::types::*;
+
+/// Framebuffer object with texture color attachments
+/// and a depth renderbuffer, deleted when dropped.
+pub struct Framebuffer {
+    pub fbo: GLuint,
+    pub colors: Vec<GLuint>,
+    pub depth: GLuint,
+}
+
+impl Framebuffer {
+    /// Creates a `width` by `height` framebuffer with `color_count`
+    /// attachments of the given format, all of them enabled as draw buffers.
+    pub fn new(
+        width: u32, height: u32, color_count: usize, color_format: GLenum,
+    ) -> Result<Self, String> {
+        Framebuffer::with_formats(width, height, color_count, color_format, gl::DEPTH_COMPONENT24)
+    }
+
+    /// Same as `new`, with a depth renderbuffer of the given format.
+    /// `DEPTH24_STENCIL8` also provides a stencil buffer.
+    pub fn with_formats(
+        width: u32, height: u32, color_count: usize, color_format: GLenum, depth_format: GLenum,
+    ) -> Result<Self, String> {
+        let mut max_attachments = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_COLOR_ATTACHMENTS, &mut max_attachments);
+        }
+        if color_count > max_attachments as usize {
+            return Err(format!("{} color attachments requested, but only {} are supported",
+                color_count, max_attachments));
+        }
+
+        let mut fb = Framebuffer {
+            fbo: 0,
+            colors: vec![0; color_count],
+            depth: 0,
+        };
+        let draw_buffers = (0 .. color_count as GLenum)
+            .map(|i| gl::COLOR_ATTACHMENT0 + i)
+            .collect::<Vec<_>>();
+        let status = unsafe {
+            gl::GenFramebuffers(1, &mut fb.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fb.fbo);
+
+            gl::GenTextures(color_count as _, fb.colors.as_mut_ptr());
+            for (&texture, &attachment) in fb.colors.iter().zip(&draw_buffers) {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexStorage2D(gl::TEXTURE_2D, 1, color_format, width as _, height as _);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, attachment, gl::TEXTURE_2D, texture, 0);
+            }
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::GenRenderbuffers(1, &mut fb.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, fb.depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, depth_format, width as _, height as _);
+            let depth_attachment = match depth_format {
+                gl::DEPTH24_STENCIL8 => gl::DEPTH_STENCIL_ATTACHMENT,
+                _ => gl::DEPTH_ATTACHMENT,
+            };
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, depth_attachment, gl::RENDERBUFFER, fb.depth);
+
+            gl::DrawBuffers(color_count as _, draw_buffers.as_ptr());
+            gl::CheckFramebufferStatus(gl::FRAMEBUFFER)
+        };
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return Err(format!("framebuffer is incomplete: 0x{:x}", status));
+        }
+        Ok(fb)
+    }
+
+}
+
+impl Drop for Framebuffer {
+    /// Deletes the GL objects, binding back the default framebuffer.
+    fn drop(&mut self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(self.colors.len() as _, self.colors.as_ptr());
+            gl::DeleteRenderbuffers(1, &self.depth);
+        }
+    }
+}
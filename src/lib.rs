@@ -0,0 +1,3515 @@
+//! Full-screen pixel rate, measured in the window of the `gl-bench`
+//! binary or in a context created by the embedding program
+//! Based on a glutin sample
+
+#[macro_use]
+extern crate bitflags;
+pub extern crate gl;
+extern crate glutin;
+extern crate png;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+
+pub mod config;
+pub mod context;
+mod debug;
+mod framebuffer;
+mod objects;
+pub mod report;
+mod screenshot;
+pub mod stats;
+mod timer;
+
+pub use config::Config;
+pub use report::BenchmarkResult;
+pub use timer::Timing;
+
+use config::{
+    ColorFormat, DepthFunc, Filter, Primitive, Profile, Resolution, SsboAccess, StreamMode,
+    UniformUpdate,
+};
+use context::{ExistingContext, Surface};
+use framebuffer::Framebuffer;
+use objects::{Buffer, Program, QueryPool, Shader, Vao};
+use report::{Environment, Format, FrameLog, Percentile, Report};
+use screenshot::Frame;
+use stats::OutlierFilter;
+use timer::{FrameTimer, FrameTimes};
+use gl::types::*;
+use std::cell::{Cell, RefCell};
+use std::ffi::CStr;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Shader sources
+static VS_SRC: &str = "
+    #version 150 core
+    uniform float u_DepthStep;
+
+    void main() {
+        switch (gl_VertexID) {
+            case 0: gl_Position = vec4(-1.0, -3.0, 0.0, 1.0); break;
+            case 1: gl_Position = vec4(3.0, 1.0, 0.0, 1.0);   break;
+            case 2: gl_Position = vec4(-1.0, 1.0, 0.0, 1.0);  break;
+            default: gl_Position = vec4(0.0, 0.0, 0.0, 1.0);
+        }
+        gl_Position.z = -float(gl_InstanceID) * u_DepthStep;
+    }"
+;
+
+static FS_SRC: &str = "
+    #version 150 core
+    out vec4 o_Color;
+
+    void main() {
+        o_Color = vec4(1.0, 1.0, 1.0, 1.0);
+    }"
+;
+
+// Translucent, so that blending can't be skipped
+static BLEND_FS_SRC: &str = "
+    #version 150 core
+    out vec4 o_Color;
+
+    void main() {
+        o_Color = vec4(1.0, 1.0, 1.0, 0.5);
+    }"
+;
+
+// Samples a repeating texture, one texel per pixel
+static TEXTURE_FS_SRC: &str = "
+    #version 150 core
+    uniform sampler2D u_Texture;
+    out vec4 o_Color;
+
+    void main() {
+        o_Color = texture(u_Texture, gl_FragCoord.xy / vec2(textureSize(u_Texture, 0)));
+    }"
+;
+
+// Samples a repeating texture mapped to a floor receding towards
+// a horizon just above the screen, so that the texture gets much
+// more minified vertically than horizontally
+static ANISO_FS_SRC: &str = "
+    #version 150 core
+    uniform sampler2D u_Texture;
+    uniform vec2 u_Resolution;
+    out vec4 o_Color;
+
+    void main() {
+        vec2 position = gl_FragCoord.xy / u_Resolution;
+        float distance = 1.0 / (1.05 - position.y);
+        o_Color = texture(u_Texture, vec2((position.x - 0.5) * distance, distance) * 4.0);
+    }"
+;
+
+// Samples a cubemap along the view direction reflected off
+// a bulge covering the screen
+static CUBEMAP_FS_SRC: &str = "
+    #version 150 core
+    uniform samplerCube u_Cubemap;
+    uniform vec2 u_Resolution;
+    out vec4 o_Color;
+
+    void main() {
+        vec3 normal = normalize(vec3(gl_FragCoord.xy / u_Resolution * 2.0 - 1.0, 1.0));
+        o_Color = texture(u_Cubemap, reflect(vec3(0.0, 0.0, -1.0), normal));
+    }"
+;
+
+// Samples a repeating 3D texture one texel per pixel, cutting
+// through its slices diagonally
+static TEXTURE_3D_FS_SRC: &str = "
+    #version 150 core
+    uniform sampler3D u_Texture;
+    out vec4 o_Color;
+
+    void main() {
+        vec3 size = vec3(textureSize(u_Texture, 0));
+        float slice = (gl_FragCoord.x + gl_FragCoord.y) * 0.5;
+        o_Color = texture(u_Texture, vec3(gl_FragCoord.xy, slice) / size);
+    }"
+;
+
+// Samples a repeating texture array one texel per pixel, like the
+// plain texture, switching to the next layer every 8x8 pixel tile
+static TEXTURE_ARRAY_FS_SRC: &str = "
+    #version 150 core
+    uniform sampler2DArray u_Texture;
+    out vec4 o_Color;
+
+    void main() {
+        ivec3 size = textureSize(u_Texture, 0);
+        ivec2 tile = ivec2(gl_FragCoord.xy) / 8;
+        float layer = float((tile.x + tile.y) % size.z);
+        o_Color = texture(u_Texture, vec3(gl_FragCoord.xy / vec2(size.xy), layer));
+    }"
+;
+
+// Writes nothing but the depth, like a shadow map
+static DEPTH_ONLY_FS_SRC: &str = "
+    #version 150 core
+
+    void main() {
+    }"
+;
+
+// Discards a `u_Fraction` of the fragments, spread evenly over
+// the screen by a low-discrepancy sequence
+static DISCARD_FS_SRC: &str = "
+    #version 150 core
+    uniform float u_Fraction;
+    out vec4 o_Color;
+
+    void main() {
+        if (fract(dot(floor(gl_FragCoord.xy), vec2(0.7548777, 0.5698403))) < u_Fraction) {
+            discard;
+        }
+        o_Color = vec4(1.0, 1.0, 1.0, 1.0);
+    }"
+;
+
+// Advances a particle per vertex by a frame, like a GPU-driven
+// particle system, for its outputs to be captured
+static FEEDBACK_VS_SRC: &str = "
+    #version 150 core
+    out vec4 v_Position;
+    out vec4 v_Velocity;
+
+    void main() {
+        float id = float(gl_VertexID);
+        vec3 velocity = vec3(sin(id * 0.37), cos(id * 0.11), sin(id * 0.73));
+        vec3 position = vec3(fract(id * 0.618034), fract(id * 0.414214), 0.0);
+        v_Position = vec4(position + velocity * 0.016, 1.0);
+        v_Velocity = vec4(velocity - vec3(0.0, 9.81 * 0.016, 0.0), 0.0);
+        gl_Position = v_Position;
+    }"
+;
+// Bytes of the outputs captured per vertex
+const FEEDBACK_VERTEX_SIZE: usize = 32;
+
+// Collapses every triangle to a point, leaving nothing to rasterize
+static EMPTY_VS_SRC: &str = "
+    #version 150 core
+
+    void main() {
+        gl_Position = vec4(0.0, 0.0, 0.0, 1.0);
+    }"
+;
+
+// Tiles the screen with a grid of points, one per vertex
+static POINTS_VS_SRC: &str = "
+    #version 150 core
+    uniform int u_Columns;
+    uniform vec2 u_CellSize;
+    uniform float u_PointSize;
+    uniform float u_DepthStep;
+
+    void main() {
+        vec2 cell = vec2(gl_VertexID % u_Columns, gl_VertexID / u_Columns);
+        gl_Position = vec4((cell + 0.5) * u_CellSize - 1.0, -float(gl_InstanceID) * u_DepthStep, 1.0);
+        gl_PointSize = u_PointSize;
+    }"
+;
+
+/// Turns the pending GL error, if any, into a message
+/// mentioning what was being done when it was raised.
+fn check_gl(context: &str) -> Result<(), String> {
+    let name = match unsafe { gl::GetError() } {
+        gl::NO_ERROR => return Ok(()),
+        gl::INVALID_ENUM => "GL_INVALID_ENUM",
+        gl::INVALID_VALUE => "GL_INVALID_VALUE",
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        other => return Err(format!("GL error 0x{:x} while {}", other, context)),
+    };
+    Err(format!("{} while {}", name, context))
+}
+
+fn shader_kind(ty: GLenum) -> &'static str {
+    match ty {
+        gl::VERTEX_SHADER => "vertex",
+        gl::FRAGMENT_SHADER => "fragment",
+        gl::COMPUTE_SHADER => "compute",
+        _ => "unknown",
+    }
+}
+
+/// Rewrites the desktop version line of a shader into the GLSL ES
+/// one with the same features, along with the default precisions
+/// that GLSL ES requires.
+fn glsl_es_src(src: &str) -> String {
+    src.replacen("#version 150 core", "#version 300 es\n    precision highp float;", 1)
+        .replacen("#version 430 core",
+            "#version 310 es\n    precision highp float;\n    precision highp image2D;", 1)
+}
+
+fn compile_shader(src: &str, ty: GLenum) -> Result<Shader, String> {
+    use std::ffi::CString;
+    use std::ptr;
+    let src = if is_gles() { glsl_es_src(src) } else { src.to_string() };
+    unsafe {
+        let shader = gl::CreateShader(ty);
+        // Attempt to compile the shader
+        let cs = CString::new(src.as_bytes()).unwrap();
+        gl::ShaderSource(shader, 1, &cs.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
+
+        // Get the compile status
+        let mut status = 0;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+        if status != gl::TRUE as GLint {
+            let mut length = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut length);
+            let mut log = vec![0u8; length as usize];
+            gl::GetShaderInfoLog(shader, length, ptr::null_mut(), log.as_mut_ptr() as *mut GLchar);
+            gl::DeleteShader(shader);
+            let log = String::from_utf8_lossy(&log);
+            return Err(format!("failed to compile the {} shader:\n{}",
+                shader_kind(ty), log.trim_end_matches('\0')));
+        }
+        Ok(Shader::from_raw(shader))
+    }
+}
+
+/// Compiles the shader in the file at `path` if there is one,
+/// or the built-in source otherwise.
+fn load_shader(path: Option<&Path>, builtin: &str, ty: GLenum) -> Result<Shader, String> {
+    let path = match path {
+        Some(path) => path,
+        None => return compile_shader(builtin, ty),
+    };
+    let src = std::fs::read_to_string(path)
+        .map_err(|e| format!("failed to read '{}': {}", path.display(), e))?;
+    compile_shader(&src, ty).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn link_program(vs: &Shader, fs: &Shader) -> Result<Program, String> {
+    link_shaders(&[vs, fs])
+}
+
+fn link_shaders(shaders: &[&Shader]) -> Result<Program, String> {
+    link_feedback_program(shaders, &[])
+}
+
+/// Links a program capturing the given outputs of its vertex
+/// shader, interleaved, with transform feedback.
+fn link_feedback_program(shaders: &[&Shader], varyings: &[&str]) -> Result<Program, String> {
+    use std::ptr;
+    let varyings = varyings
+        .iter()
+        .map(|&name| std::ffi::CString::new(name).unwrap())
+        .collect::<Vec<_>>();
+    let varying_ptrs = varyings.iter().map(|name| name.as_ptr()).collect::<Vec<_>>();
+    unsafe {
+        let program = gl::CreateProgram();
+        for shader in shaders {
+            gl::AttachShader(program, shader.as_raw());
+        }
+        if !varyings.is_empty() {
+            gl::TransformFeedbackVaryings(program, varying_ptrs.len() as _, varying_ptrs.as_ptr(),
+                gl::INTERLEAVED_ATTRIBS);
+        }
+        gl::LinkProgram(program);
+        // Get the link status
+        let mut status = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut status);
+        if status != gl::TRUE as GLint {
+            let mut length = 0;
+            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut length);
+            let mut log = vec![0u8; length as usize];
+            gl::GetProgramInfoLog(program, length, ptr::null_mut(), log.as_mut_ptr() as *mut GLchar);
+            gl::DeleteProgram(program);
+            let log = String::from_utf8_lossy(&log);
+            return Err(format!("failed to link the program:\n{}", log.trim_end_matches('\0')));
+        }
+        Ok(Program::from_raw(program))
+    }
+}
+
+bitflags! {
+    struct Flags: u32 {
+        const CLEAR = 1 << 0;
+        const DRAW = 1 << 1;
+    }
+}
+
+// Covers the screen with horizontal lines, two vertices each
+static LINES_VS_SRC: &str = "
+    #version 150 core
+    uniform float u_RowHeight;
+    uniform float u_DepthStep;
+
+    void main() {
+        float y = (float(gl_VertexID / 2) + 0.5) * u_RowHeight - 1.0;
+        float z = -float(gl_InstanceID) * u_DepthStep;
+        gl_Position = vec4(gl_VertexID % 2 == 0 ? -1.0 : 1.0, y, z, 1.0);
+    }"
+;
+
+// Tiles the screen with a grid of cells, two triangles each
+static GRID_VS_SRC: &str = "
+    #version 150 core
+    uniform int u_Columns;
+    uniform vec2 u_CellSize;
+    uniform float u_DepthStep;
+
+    const vec2 CORNERS[6] = vec2[6](
+        vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 1.0),
+        vec2(0.0, 1.0), vec2(1.0, 0.0), vec2(1.0, 1.0)
+    );
+
+    void main() {
+        int cell = gl_VertexID / 6;
+        vec2 corner = vec2(cell % u_Columns, cell / u_Columns) + CORNERS[gl_VertexID % 6];
+        gl_Position = vec4(corner * u_CellSize - 1.0, -float(gl_InstanceID) * u_DepthStep, 1.0);
+    }"
+;
+
+// Places a triangle of `u_TriangleSize` at the corner of every cell of a grid
+static CORNERS_VS_SRC: &str = "
+    #version 150 core
+    uniform int u_Columns;
+    uniform vec2 u_CellSize;
+    uniform vec2 u_TriangleSize;
+
+    const vec2 CORNERS[3] = vec2[3](vec2(0.0, 0.0), vec2(1.0, 0.0), vec2(0.0, 1.0));
+
+    void main() {
+        int cell = gl_VertexID / 3;
+        vec2 origin = vec2(cell % u_Columns, cell / u_Columns) * u_CellSize - 1.0;
+        gl_Position = vec4(origin + CORNERS[gl_VertexID % 3] * u_TriangleSize, 0.0, 1.0);
+    }"
+;
+
+// The full-screen triangle, counter-clockwise, followed by
+// the same one wound clockwise
+static CULL_VS_SRC: &str = "
+    #version 150 core
+
+    const vec2 POSITIONS[6] = vec2[6](
+        vec2(-1.0, -3.0), vec2(3.0, 1.0), vec2(-1.0, 1.0),
+        vec2(-1.0, -3.0), vec2(-1.0, 1.0), vec2(3.0, 1.0)
+    );
+
+    void main() {
+        gl_Position = vec4(POSITIONS[gl_VertexID], 0.0, 1.0);
+    }"
+;
+
+// Same grid as GRID_VS_SRC, fetching the vertices from buffers
+static ATTRIBS_VS_SRC: &str = "
+    #version 150 core
+    uniform float u_DepthStep;
+    in vec2 a_Position;
+    in vec4 a_Color;
+    in vec3 a_Normal;
+    out vec4 v_Color;
+
+    void main() {
+        v_Color = a_Color * max(a_Normal.z, 0.0);
+        gl_Position = vec4(a_Position, -float(gl_InstanceID) * u_DepthStep, 1.0);
+    }"
+;
+
+static ATTRIBS_FS_SRC: &str = "
+    #version 150 core
+    in vec4 v_Color;
+    out vec4 o_Color;
+
+    void main() {
+        o_Color = v_Color;
+    }"
+;
+
+/// Interleaved vertex of the attribute fetch test.
+#[repr(C)]
+struct Vertex {
+    position: [f32; 2],
+    color: [u8; 4],
+    normal: [f32; 3],
+}
+
+/// Triangles per instance of the attribute fetch test, unless
+/// `tris_per_draw` is given.
+const ATTRIBS_TRIANGLES: usize = 8192;
+
+/// Regions of the persistently mapped buffer of the streaming test,
+/// written in turn so that the CPU can fill one of them while the
+/// GPU still reads the others.
+const STREAM_REGIONS: usize = 3;
+
+// Writes every pixel of an image, one invocation per pixel
+static COMPUTE_SRC: &str = "
+    #version 430 core
+    layout(local_size_x = 8, local_size_y = 8) in;
+    layout(rgba8, binding = 0) uniform writeonly image2D u_Image;
+
+    void main() {
+        imageStore(u_Image, ivec2(gl_GlobalInvocationID.xy), vec4(1.0, 1.0, 1.0, 1.0));
+    }"
+;
+const COMPUTE_GROUP_SIZE: u32 = 8;
+// Invocations per work group, and work groups dispatched, of the
+// storage buffer test
+const SSBO_GROUP_SIZE: u32 = 256;
+const SSBO_GROUPS: u32 = 4096;
+
+// From EXT_disjoint_timer_query, which the desktop bindings lack
+const GPU_DISJOINT_EXT: GLenum = 0x8FBB;
+// From GLES, where the point size range has this name instead
+const ALIASED_POINT_SIZE_RANGE: GLenum = 0x846D;
+// From ARB_pipeline_statistics_query, core since GL 4.6
+const FRAGMENT_SHADER_INVOCATIONS_ARB: GLenum = 0x82F4;
+// From EXT_texture_filter_anisotropic, core since GL 4.6
+const TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY_EXT: GLenum = 0x84FF;
+// From NVX_gpu_memory_info and ATI_meminfo, in KiB
+const GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX: GLenum = 0x9048;
+const GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX: GLenum = 0x9049;
+const TEXTURE_FREE_MEMORY_ATI: GLenum = 0x87FC;
+/// Batches of frames thrown away on disjoint events before giving up.
+const MAX_DISJOINT_RETRIES: usize = 5;
+/// Frame time beyond which a timer result is more likely garbage,
+/// such as a wrapped counter, than a real measurement.
+const MAX_PLAUSIBLE_FRAME_NS: u64 = 60_000_000_000;
+
+/// Fragment shader writing to `count` color outputs.
+fn mrt_fs_src(count: usize) -> String {
+    let writes = (0 .. count)
+        .map(|i| format!("o_Colors[{}] = vec4(1.0, 1.0, 1.0, 1.0);", i))
+        .collect::<Vec<_>>();
+    format!("
+    #version 150 core
+    out vec4 o_Colors[{}];
+
+    void main() {{
+        {}
+    }}", count, writes.join("\n        "))
+}
+
+/// Fragment shader storing its color into an image of the given
+/// format, the color format names matching the GLSL ones.
+fn image_store_fs_src(format: ColorFormat) -> String {
+    format!("
+    #version 430 core
+    layout({}, binding = 0) uniform writeonly image2D u_Image;
+
+    void main() {{
+        imageStore(u_Image, ivec2(gl_FragCoord.xy), vec4(1.0, 1.0, 1.0, 1.0));
+    }}", format)
+}
+
+/// Compute shader going through the elements of a storage buffer
+/// with the given access, a grid's worth of them at a time. The reads
+/// are only kept from being optimized out by a write that never happens.
+fn ssbo_cs_src(access: SsboAccess) -> String {
+    let (body, tail) = match access {
+        SsboAccess::Read => ("sum += b_Data[i];", "if (sum.x < 0.0) b_Data[0] = sum;"),
+        SsboAccess::Write => ("b_Data[i] = vec4(float(i));", ""),
+        SsboAccess::ReadWrite => ("b_Data[i] = b_Data[i] * 0.5 + 1.0;", ""),
+    };
+    format!("
+    #version 430 core
+    layout(local_size_x = {}) in;
+    layout(std430, binding = 0) buffer Data {{ vec4 b_Data[]; }};
+    uniform uint u_Count;
+
+    void main() {{
+        uint stride = gl_NumWorkGroups.x * gl_WorkGroupSize.x;
+        vec4 sum = vec4(0.0);
+        for (uint i = gl_GlobalInvocationID.x; i < u_Count; i += stride) {{
+            {}
+        }}
+        {}
+    }}", SSBO_GROUP_SIZE, body, tail)
+}
+
+/// Fragment shader summing `count` vec4 uniforms, declared as
+/// a plain array or in a uniform block.
+fn uniform_fs_src(count: usize, mode: UniformUpdate) -> String {
+    let declaration = match mode {
+        UniformUpdate::Calls => format!("uniform vec4 u_Values[{}];", count),
+        UniformUpdate::Ubo => format!("layout(std140) uniform Values {{ vec4 u_Values[{}]; }};", count),
+    };
+    format!("
+    #version 150 core
+    {}
+    out vec4 o_Color;
+
+    void main() {{
+        vec4 sum = vec4(0.0);
+        for (int i = 0; i < {}; ++i) {{
+            sum += u_Values[i];
+        }}
+        o_Color = sum;
+    }}", declaration, count)
+}
+
+/// Fragment shader doing `ops` multiply-adds per fragment. Both the
+/// seed and the operands are opaque to the compiler, so neither the
+/// loop nor its result can be folded.
+fn alu_fs_src(ops: usize) -> String {
+    format!("
+    #version 150 core
+    uniform float u_Scale;
+    uniform float u_Bias;
+    out vec4 o_Color;
+
+    void main() {{
+        vec4 acc = vec4(gl_FragCoord.xy, 0.0, 1.0);
+        for (int i = 0; i < {}; ++i) {{
+            acc = acc * u_Scale + u_Bias;
+        }}
+        o_Color = acc;
+    }}", ops)
+}
+
+/// What a test draws every frame, and which part of it gets timed.
+struct Test {
+    name: String,
+    clear_mask: GLenum,
+    flags: Flags,
+    primitive: GLenum,
+    vertex_count: usize,
+    num_draws: usize,
+    /// Untimed work done before every batch of frames, including the
+    /// ones discarded and run again.
+    start_batch: Option<Box<dyn Fn()>>,
+    /// Untimed work done at the start of every frame.
+    prepare: Option<Box<dyn Fn()>>,
+    /// Timed work replacing the instanced draw call.
+    work: Option<Box<dyn Fn()>>,
+    /// Whether the samples passing the depth test get counted,
+    /// with `--samples-passed`.
+    count_samples: bool,
+}
+
+impl Test {
+    /// A test drawing `num_draws` instances of a full-screen triangle.
+    fn new(name: &str, clear_mask: GLenum, num_draws: usize, flags: Flags) -> Self {
+        Test {
+            name: name.to_string(),
+            clear_mask,
+            flags,
+            primitive: gl::TRIANGLES,
+            vertex_count: 3,
+            num_draws,
+            start_batch: None,
+            prepare: None,
+            work: None,
+            count_samples: false,
+        }
+    }
+}
+
+/// Receives the index and the time of every frame.
+type SampleCallback<'a> = Box<dyn FnMut(usize, u64) + 'a>;
+
+/// GL objects and settings shared by all the tests, deleted when dropped.
+pub struct Benchmark<'a> {
+    config: &'a Config,
+    context: &'a dyn Surface,
+    /// Framebuffer the tests render to, 0 for the window's.
+    target_fbo: Cell<GLuint>,
+    /// Size of the offscreen framebuffer, if the tests render to one.
+    target_size: Cell<Option<(u32, u32)>>,
+    queries: QueryPool,
+    /// Program drawing the full-screen triangle, bound between tests.
+    program: Program,
+    /// Vertex shader of the full-screen triangle.
+    vs: Shader,
+    /// Fragment shader writing a constant color.
+    fs: Shader,
+    /// Whether the frames are timed on the CPU, for lack of timer queries.
+    cpu_timing: Cell<bool>,
+    /// Whether the timer queries can be invalidated by disjoint events,
+    /// as with `EXT_disjoint_timer_query` on GLES.
+    disjoint_timer: bool,
+    /// Whether GL objects can be labeled, with GL 4.3 or `KHR_debug`.
+    khr_debug: bool,
+    /// Set once the window was closed, interrupting the tests.
+    closed: Cell<bool>,
+    /// Last frame of the latest test that ran them all, for the screenshot.
+    last_frame: RefCell<Option<Frame>>,
+    /// Queries of the primitives generated and of the fragment shader
+    /// invocations, if pipeline statistics are enabled and supported.
+    stats_queries: Option<QueryPool>,
+    /// Occlusion query of the tests counting their samples, if enabled.
+    samples_query: Option<QueryPool>,
+    /// Attributeless vertex array bound while the tests run.
+    vao: Vao,
+    /// Offscreen framebuffer the tests render to, if not the surface's.
+    target: Option<Framebuffer>,
+    /// Name of the color and depth test, with the MSAA sample count.
+    color_name: String,
+    renderer: String,
+    version: String,
+    environment: Environment,
+    /// Called with every frame's time, once the results of a test are in.
+    on_sample: RefCell<Option<SampleCallback<'a>>>,
+    /// Log of every frame's time, with `--frames-out`.
+    frame_log: RefCell<Option<FrameLog>>,
+}
+
+impl<'a> Benchmark<'a> {
+    fn run(&self, test: &Test) -> Result<BenchmarkResult, String> {
+        let mut on_sample = self.on_sample.borrow_mut();
+        run_tests(test, self, on_sample.as_mut().map(|f| &mut **f as &mut dyn FnMut(usize, u64)))
+    }
+
+    /// Applies the window resizes that happened during a test,
+    /// so that the next one runs at the new size.
+    fn apply_resize(&self) {
+        if !self.context.apply_resize() || self.target_size.get().is_some() {
+            return;
+        }
+        self.bind_target();
+        if self.config.prints_runs() {
+            let (width, height) = self.size();
+            println!("Resized to {}x{}", width, height);
+        }
+    }
+
+    /// Size of the render target, in pixels.
+    fn size(&self) -> (u32, u32) {
+        self.target_size.get().unwrap_or_else(|| self.context.size())
+    }
+
+    fn hidpi_factor(&self) -> f32 {
+        match self.target_size.get() {
+            Some(_) => 1.0,
+            None => self.context.hidpi_factor(),
+        }
+    }
+
+    /// Makes the tests render to the given framebuffer object
+    /// of the given size, or to the window with no size.
+    fn set_target(&self, fbo: GLuint, size: Option<(u32, u32)>) {
+        self.target_fbo.set(fbo);
+        self.target_size.set(size);
+        self.bind_target();
+    }
+
+    /// Binds back the framebuffer the tests render to, and its viewport.
+    fn bind_target(&self) {
+        let (width, height) = self.size();
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.target_fbo.get());
+            gl::Viewport(0, 0, width as _, height as _);
+        }
+    }
+}
+
+/// Checks whether the context provides at least GL `major.minor`.
+fn supports_gl(major: GLint, minor: GLint) -> bool {
+    let (mut actual_major, mut actual_minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut actual_major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut actual_minor);
+    }
+    (actual_major, actual_minor) >= (major, minor)
+}
+
+/// Checks whether the context is an OpenGL ES one.
+fn is_gles() -> bool {
+    let version = unsafe { CStr::from_ptr(gl::GetString(gl::VERSION) as _) };
+    version.to_bytes().starts_with(b"OpenGL ES")
+}
+
+/// Name of the profile the context was created with.
+fn context_profile() -> &'static str {
+    if is_gles() {
+        return "es";
+    }
+    // the profile mask only exists since GL 3.2, older contexts
+    // have everything the compatibility profile does
+    if !supports_gl(3, 2) {
+        return "compat";
+    }
+    let mut mask = 0;
+    unsafe { gl::GetIntegerv(gl::CONTEXT_PROFILE_MASK, &mut mask) };
+    if mask as GLuint & gl::CONTEXT_CORE_PROFILE_BIT != 0 {
+        "core"
+    } else {
+        "compat"
+    }
+}
+
+/// Queries the limits of the implementation reported with the results.
+fn query_environment() -> Environment {
+    let integer = |name| {
+        let mut value = 0;
+        unsafe { gl::GetIntegerv(name, &mut value) };
+        value
+    };
+    // AMD only tells how much is free, its first value being the total
+    let (vram_total_mb, vram_available_mb) = if has_extension("GL_NVX_gpu_memory_info") {
+        let total = integer(GPU_MEMORY_INFO_TOTAL_AVAILABLE_MEMORY_NVX);
+        let available = integer(GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX);
+        (Some(total as u64 / 1024), Some(available as u64 / 1024))
+    } else if has_extension("GL_ATI_meminfo") {
+        let mut free = [0; 4];
+        unsafe { gl::GetIntegerv(TEXTURE_FREE_MEMORY_ATI, free.as_mut_ptr()) };
+        (None, Some(free[0] as u64 / 1024))
+    } else {
+        (None, None)
+    };
+    Environment {
+        vram_total_mb,
+        vram_available_mb,
+        max_texture_size: integer(gl::MAX_TEXTURE_SIZE),
+        max_samples: integer(gl::MAX_SAMPLES),
+        max_color_attachments: integer(gl::MAX_COLOR_ATTACHMENTS),
+        max_vertex_attribs: integer(gl::MAX_VERTEX_ATTRIBS),
+    }
+}
+
+/// Checks whether compute shaders are available, as of GL 4.3 or GLES 3.1.
+fn supports_compute() -> bool {
+    if is_gles() { supports_gl(3, 1) } else { supports_gl(4, 3) }
+}
+
+/// Checks whether immutable textures are available, as of GL 4.2 or GLES 3.0.
+fn supports_tex_storage() -> bool {
+    is_gles() || supports_gl(4, 2) || has_extension("GL_ARB_texture_storage")
+}
+
+/// Names of the extensions the context exposes, one by one, which
+/// core profiles require rather than the whole `GL_EXTENSIONS` string.
+/// The strings belong to the context.
+fn extensions() -> impl Iterator<Item = &'static CStr> {
+    let mut count = 0;
+    unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count) };
+    (0 .. count as GLuint).map(|i| unsafe { CStr::from_ptr(gl::GetStringi(gl::EXTENSIONS, i) as _) })
+}
+
+/// Checks whether the context exposes the named extension.
+fn has_extension(name: &str) -> bool {
+    extensions().any(|extension| extension.to_bytes() == name.as_bytes())
+}
+
+/// Reads and resets the disjoint flag, set when something like a context
+/// switch or a power state change invalidated the pending timer queries.
+fn timer_disjoint() -> bool {
+    let mut disjoint = 0;
+    unsafe { gl::GetIntegerv(GPU_DISJOINT_EXT, &mut disjoint) };
+    disjoint != 0
+}
+
+fn uniform_location(program: &Program, name: &str) -> GLint {
+    let name = std::ffi::CString::new(name).unwrap();
+    unsafe { gl::GetUniformLocation(program.as_raw(), name.as_ptr()) }
+}
+
+/// Moves every instance drawn by the bound program slightly closer
+/// than the previous one, so that all `num_draws` of them pass the
+/// depth test. Programs default to drawing them all at the same depth.
+fn set_overdraw(program: &Program, num_draws: usize) {
+    unsafe {
+        gl::Uniform1f(uniform_location(program, "u_DepthStep"), 1.0 / num_draws as f32);
+    }
+}
+
+/// Runs the frames of a test and computes its timings, passing the
+/// time of every frame to `on_sample` along with its index, warmup
+/// included, once they are all collected. The timer results of every
+/// frame are read `QUERY_RING_FRAMES` frames after it was issued.
+fn run_tests(
+    test: &Test, bench: &Benchmark, on_sample: Option<&mut dyn FnMut(usize, u64)>,
+) -> Result<BenchmarkResult, String> {
+    let (config, context, queries) = (bench.config, bench.context, &bench.queries);
+    let (test_name, flags, num_draws) = (&test.name, test.flags, test.num_draws);
+    let (width, height) = bench.size();
+    let [scissor_x, scissor_y, scissor_width, scissor_height] = config.scissor
+        .unwrap_or([1, 1, (width / 2) as i32, (height / 2) as i32]);
+    let cpu_timing = bench.cpu_timing.get();
+    let samples_query = bench.samples_query
+        .as_ref()
+        .filter(|_| test.count_samples)
+        .map(|query| query[0]);
+    let check_disjoint = bench.disjoint_timer && !cpu_timing;
+    let max_duration = config.max_duration.map(Duration::from_secs_f64);
+    let mut retries = 0;
+    let times = loop {
+        if check_disjoint {
+            // forget any disjoint event from before this batch
+            timer_disjoint();
+        }
+        if let Some(ref start_batch) = test.start_batch {
+            start_batch();
+        }
+        let mut timer = FrameTimer::new(queries, config.timing, cpu_timing, flags);
+        let start = Instant::now();
+        for frame in 0 .. config.num_queries {
+            if let Some(ref prepare) = test.prepare {
+                prepare();
+            }
+            timer.before_clear();
+            unsafe {
+                if config.clear_scissored {
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(scissor_x, scissor_y, scissor_width, scissor_height);
+                }
+                gl::Clear(test.clear_mask);
+                if config.clear_scissored {
+                    gl::Disable(gl::SCISSOR_TEST);
+                }
+            }
+            timer.after_clear();
+
+            unsafe {
+                // the statistics of every frame overwrite the previous
+                // ones, leaving those of the last
+                if let Some(ref stats) = bench.stats_queries {
+                    gl::BeginQuery(gl::PRIMITIVES_GENERATED, stats[0]);
+                    gl::BeginQuery(FRAGMENT_SHADER_INVOCATIONS_ARB, stats[1]);
+                }
+                if let Some(query) = samples_query {
+                    gl::BeginQuery(gl::SAMPLES_PASSED, query);
+                }
+                match test.work {
+                    Some(ref work) => work(),
+                    None => gl::DrawArraysInstanced(
+                        test.primitive, 0, test.vertex_count as _, num_draws as _,
+                    ),
+                }
+                if bench.stats_queries.is_some() {
+                    gl::EndQuery(gl::PRIMITIVES_GENERATED);
+                    gl::EndQuery(FRAGMENT_SHADER_INVOCATIONS_ARB);
+                }
+                if samples_query.is_some() {
+                    gl::EndQuery(gl::SAMPLES_PASSED);
+                }
+            }
+            timer.after_draw();
+
+            let last = frame + 1 == config.num_queries
+                || max_duration.is_some_and(|max| start.elapsed() >= max);
+            // the back buffer is undefined once swapped, so the last frame
+            // has to be read before
+            if config.screenshot.is_some() && last {
+                *bench.last_frame.borrow_mut() = Some(Frame::read(width, height));
+            }
+            context.swap_buffers()?;
+            if context.poll_events() {
+                bench.closed.set(true);
+                return Err(format!("the window was closed while running '{}'", test_name));
+            }
+            if last {
+                break;
+            }
+        }
+        bench.apply_resize();
+        // query objects only exist once used, so they get labeled after the
+        // fact, with the test they were last used by
+        if bench.khr_debug && !cpu_timing {
+            for (i, &query) in timer.issued_queries().iter().enumerate() {
+                debug::label(gl::QUERY, query, &format!("'{}' query {}", test_name, i));
+            }
+        }
+        // errors stick around until queried, so one check covers all the frames
+        check_gl(&format!("running '{}'", test_name))?;
+
+        let times = timer.results();
+        if !check_disjoint || !timer_disjoint() {
+            break times;
+        }
+        if retries == MAX_DISJOINT_RETRIES {
+            return Err(format!("timer queries of '{}' kept being disjoint after {} retries",
+                test_name, retries));
+        }
+        retries += 1;
+        eprintln!("warning: discarding the frames of '{}' after a disjoint timer event", test_name);
+    };
+
+    let FrameTimes { frames: all_samples, splits, wait_ns } = times;
+    let pipeline_stats = bench.stats_queries
+        .as_ref()
+        .map(|stats| (query_result(stats[0]), query_result(stats[1])));
+    let samples_passed = samples_query.map(query_result);
+    if !cpu_timing && all_samples.iter().all(|&sample| sample == 0) {
+        eprintln!("warning: timer queries returned nothing, falling back to CPU timing");
+        bench.cpu_timing.set(true);
+        return run_tests(test, bench, on_sample);
+    }
+    if let Some(on_sample) = on_sample {
+        for (frame, &ns) in all_samples.iter().enumerate() {
+            on_sample(frame, ns);
+        }
+    }
+    if let Some(ref mut log) = *bench.frame_log.borrow_mut() {
+        log.write(test_name, &all_samples, config.warmup_frames)?;
+    }
+    if let Some(&longest) = all_samples.iter().max().filter(|&&ns| ns > MAX_PLAUSIBLE_FRAME_NS) {
+        eprintln!("warning: implausibly long frame of {:.1} s in '{}', \
+            the timer results may be corrupt", longest as f64 / 1.0e9, test_name);
+    }
+    // fewer frames than queried may have made it within the time limit
+    let num_frames = all_samples.len();
+    let measured = if num_frames > config.warmup_frames + config.cooldown_frames {
+        config.measured_range(num_frames)
+    } else {
+        eprintln!("warning: only {} frames of '{}' completed in time, \
+            measuring all of them including the warmup", num_frames, test_name);
+        0 .. num_frames
+    };
+    let samples = match config.outlier_filter {
+        OutlierFilter::Warmup => all_samples[measured.clone()].to_vec(),
+        OutlierFilter::Iqr => stats::filter_iqr(&all_samples),
+    };
+    let outliers = all_samples.len() - samples.len();
+    // the outlier filter drops whole frames, so the clear and draw
+    // averages stick to the frames outside of the warmup and cooldown
+    let split_means = splits.map(|(clears, draws)| {
+        let mean = |times: &[u64]| times.iter().sum::<u64>() / times.len() as u64;
+        (mean(&clears[measured.clone()]), mean(&draws[measured.clone()]))
+    });
+    debug_assert!(config.outlier_filter != OutlierFilter::Warmup ||
+        samples.len() == measured.len());
+    let total_time = samples.iter().sum::<u64>();
+
+    // the size is in physical pixels, so scaling it by the
+    // hiDPI factor would count them twice
+    let pixel_count = width as u64 * height as u64;
+    let total_draws = (samples.len() * num_draws) as u64;
+    let fullscreen_time = total_time / total_draws;
+    let megapixel_time = stats::per_megapixel(fullscreen_time, pixel_count);
+    let median_time = stats::median(&samples) / num_draws as u64;
+    let std_dev_time = stats::std_dev(&samples) / num_draws as f64;
+    let min_frame_time = samples.iter().cloned().min().unwrap_or(0);
+    let max_frame_time = samples.iter().cloned().max().unwrap_or(0);
+    let mut sorted = samples.clone();
+    sorted.sort();
+    let percentiles = config.percentiles
+        .iter()
+        .map(|&percent| Percentile {
+            percent,
+            ns: (stats::percentile(&sorted, percent) / num_draws as f64) as u64,
+        })
+        .collect::<Vec<_>>();
+
+    if config.prints_runs() {
+        println!("Tested '{}' with {} samples of {} instances{}",
+            test_name, num_frames, num_draws,
+            if cpu_timing { " (CPU-timed)" } else { "" });
+        if config.outlier_filter == OutlierFilter::Iqr {
+            println!("\tdropped {} outliers", outliers);
+        }
+        println!("\tfull-screen time: {:.2} ms", fullscreen_time as f32 / 1.0e6);
+        println!("\tmedian full-screen time: {:.2} ms", median_time as f32 / 1.0e6);
+        println!("\tstandard deviation: ± {:.2} ms", std_dev_time / 1.0e6);
+        println!("\tframe min/max: {:.2} / {:.2} ms",
+            min_frame_time as f32 / 1.0e6, max_frame_time as f32 / 1.0e6);
+        if !percentiles.is_empty() {
+            let list = percentiles
+                .iter()
+                .map(|p| format!("p{} {:.2} ms", p.percent, p.ns as f32 / 1.0e6))
+                .collect::<Vec<_>>();
+            println!("\tfull-screen percentiles: {}", list.join(", "));
+        }
+        println!("\tmega-pixel time: {} mcs", megapixel_time / 1000);
+        if config.histogram {
+            print_histogram("frame times", &samples);
+        }
+        if !cpu_timing {
+            println!("\twaited for the results: {:.2} ms", wait_ns as f32 / 1.0e6);
+        }
+        if let Some((clear, draw)) = split_means {
+            println!("\tclear/draw frame time: {:.2} / {:.2} ms",
+                clear as f32 / 1.0e6, draw as f32 / 1.0e6);
+        }
+        if let Some((primitives, fragments)) = pipeline_stats {
+            println!("\tprimitives generated: {} per frame", primitives);
+            let frame_ns = (fullscreen_time * num_draws as u64) as f64;
+            println!("\tfragment shader invocations: {} per frame, {:.2} per pixel, {:.3} ns each",
+                fragments, fragments as f64 / pixel_count as f64, frame_ns / fragments.max(1) as f64);
+        }
+        if let Some(samples) = samples_passed {
+            println!("\tsamples passed: {} per frame, {:.2} per pixel",
+                samples, samples as f64 / pixel_count as f64);
+        }
+    }
+
+    Ok(BenchmarkResult {
+        name: test_name.clone(),
+        samples: samples.len(),
+        num_draws,
+        fullscreen_ns: fullscreen_time as u64,
+        megapixel_ns: megapixel_time as u64,
+        median_ns: median_time as u64,
+        std_dev_ns: std_dev_time as u64,
+        min_frame_ns: min_frame_time as u64,
+        max_frame_ns: max_frame_time as u64,
+        percentiles,
+        outliers,
+        cpu_timed: cpu_timing,
+        clear_ns: split_means.map(|(clear, _)| clear),
+        draw_ns: split_means.map(|(_, draw)| draw),
+        repeat_std_dev_ns: None,
+        query_wait_ns: wait_ns,
+        primitives: pipeline_stats.map(|(primitives, _)| primitives),
+        fragment_invocations: pipeline_stats.map(|(_, fragments)| fragments),
+        samples_passed,
+    })
+}
+
+/// Prints the frame times as rows of a bar chart, from the fastest up.
+fn print_histogram(title: &str, samples: &[u64]) {
+    const BINS: usize = 20;
+    const BAR_WIDTH: usize = 40;
+    let (counts, min, width) = stats::histogram(samples, BINS);
+    let highest = counts.iter().cloned().max().unwrap_or(0).max(1);
+    println!("\t{}:", title);
+    for (i, &count) in counts.iter().enumerate() {
+        let start = min as f64 + i as f64 * width;
+        println!("\t{:8.3} ms |{:<bar$}| {}",
+            start / 1.0e6, "#".repeat(count * BAR_WIDTH / highest), count, bar = BAR_WIDTH);
+    }
+}
+
+/// Drops the outliers of the per-frame `samples` some tests measure
+/// on the side, the way the frame times are, returning them sorted.
+fn trimmed_samples(config: &Config, samples: &[u64]) -> Vec<u64> {
+    let num_frames = samples.len();
+    let mut trimmed = match config.outlier_filter {
+        OutlierFilter::Warmup if num_frames > config.warmup_frames + config.cooldown_frames => {
+            samples[config.measured_range(num_frames)].to_vec()
+        }
+        OutlierFilter::Warmup => samples.to_vec(),
+        OutlierFilter::Iqr => stats::filter_iqr(samples),
+    };
+    trimmed.sort();
+    trimmed
+}
+
+/// Prints the distribution of durations measured on the CPU, once
+/// sorted by `trimmed_samples`.
+fn print_cpu_durations(config: &Config, what: &str, sorted: &[u64]) {
+    let ms = |ns: f64| ns / 1.0e6;
+    println!("\t{}: median {:.3} ms, min/max {:.3} / {:.3} ms", what,
+        ms(stats::median(sorted) as f64),
+        ms(sorted.first().cloned().unwrap_or(0) as f64),
+        ms(sorted.last().cloned().unwrap_or(0) as f64));
+    if !config.percentiles.is_empty() {
+        let list = config.percentiles
+            .iter()
+            .map(|&p| format!("p{} {:.3} ms", p, ms(stats::percentile(sorted, p))))
+            .collect::<Vec<_>>();
+        println!("\t{} percentiles: {}", what, list.join(", "));
+    }
+    if config.histogram {
+        print_histogram(&format!("{} times", what), sorted);
+    }
+}
+
+/// Waits for the result of a query and returns it.
+fn query_result(query: GLuint) -> u64 {
+    let mut result = 0;
+    unsafe { gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut result) };
+    result
+}
+
+/// Runs `work` within a query of the given target, such as
+/// `GL_SAMPLES_PASSED`, and waits for its result.
+fn count_query(target: GLenum, work: &dyn Fn()) -> u64 {
+    let query = QueryPool::new(1);
+    unsafe {
+        gl::BeginQuery(target, query[0]);
+        work();
+        gl::EndQuery(target);
+    }
+    query_result(query[0])
+}
+
+/// Draws one frame of the depth rejection test, counting the fragment
+/// shader invocations, or without pipeline statistics the samples passing
+/// the depth test. Warns when the instances behind the first one don't
+/// seem to be rejected, before shading with early-Z in the first case.
+fn check_depth_rejection(bench: &Benchmark) -> Result<(), String> {
+    let (width, height) = bench.size();
+    let num_rejects = bench.config.num_rejects;
+    let draw = || unsafe {
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, num_rejects as _);
+    };
+    let pixels = width as u64 * height as u64;
+    let (what, count, expected) = if supports_gl(4, 6) ||
+        has_extension("GL_ARB_pipeline_statistics_query") {
+        ("fragment shader invocations", count_query(FRAGMENT_SHADER_INVOCATIONS_ARB, &draw), pixels)
+    } else if !is_gles() {
+        let mut samples = 0;
+        unsafe { gl::GetIntegerv(gl::SAMPLES, &mut samples) };
+        let expected = pixels * samples.max(1) as u64;
+        ("samples passed", count_query(gl::SAMPLES_PASSED, &draw), expected)
+    } else {
+        return Ok(());
+    };
+    check_gl("counting the rejected fragments")?;
+
+    if bench.config.prints_runs() {
+        println!("\t{}: {} per frame, {:.2} per pixel", what, count, count as f64 / pixels as f64);
+    }
+    // a bit of slack for the helper invocations along the edges
+    if count > expected * 3 / 2 {
+        eprintln!("warning: got {} {} where about {} were expected, \
+            the depth rejected instances may not be culled before shading", count, what, expected);
+    }
+    Ok(())
+}
+
+/// Largest size of the points the vertex shader can set.
+fn max_point_size() -> f32 {
+    let name = if is_gles() { ALIASED_POINT_SIZE_RANGE } else { gl::POINT_SIZE_RANGE };
+    let mut range = [0.0; 2];
+    unsafe { gl::GetFloatv(name, range.as_mut_ptr()) };
+    range[1]
+}
+
+/// Measures points tiling the screen, each covering a square of
+/// `point_size` pixels, instead of a full-screen triangle. Point
+/// sizes beyond the driver's limit are clamped to it.
+fn test_points(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let (width, height) = bench.size();
+    let vs = compile_shader(POINTS_VS_SRC, gl::VERTEX_SHADER)?;
+    let points_program = link_program(&vs, &bench.fs)?;
+    let max_size = max_point_size();
+    let mut cell = bench.config.point_size;
+    if cell > max_size {
+        eprintln!("warning: point size {} exceeds the maximum of {}, clamping it", cell, max_size);
+        cell = max_size;
+    }
+    let columns = (width as f32 / cell).ceil() as usize;
+    let rows = (height as f32 / cell).ceil() as usize;
+    unsafe {
+        gl::UseProgram(points_program.as_raw());
+        gl::Uniform1i(uniform_location(&points_program, "u_Columns"), columns as _);
+        gl::Uniform2f(uniform_location(&points_program, "u_CellSize"),
+            2.0 * cell / width as f32, 2.0 * cell / height as f32);
+        gl::Uniform1f(uniform_location(&points_program, "u_PointSize"), cell);
+        // GLES always takes the point size from the vertex shader
+        if !is_gles() {
+            gl::Enable(gl::PROGRAM_POINT_SIZE);
+        }
+    }
+    set_overdraw(&points_program, bench.config.overdraw);
+
+    let mut test = Test::new(
+        &format!("points {}px", cell),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    );
+    test.primitive = gl::POINTS;
+    test.vertex_count = columns * rows;
+    let result = bench.run(&test);
+
+    unsafe {
+        if !is_gles() {
+            gl::Disable(gl::PROGRAM_POINT_SIZE);
+        }
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Measures horizontal lines of `line_width` pixels stacked
+/// to cover the screen, instead of a full-screen triangle.
+fn test_lines(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let (_, height) = bench.size();
+    let vs = compile_shader(LINES_VS_SRC, gl::VERTEX_SHADER)?;
+    let lines_program = link_program(&vs, &bench.fs)?;
+    let line_width = bench.config.line_width;
+    let rows = (height as f32 / line_width).ceil() as usize;
+    unsafe {
+        gl::UseProgram(lines_program.as_raw());
+        gl::Uniform1f(uniform_location(&lines_program, "u_RowHeight"),
+            2.0 * line_width / height as f32);
+        gl::LineWidth(line_width);
+    }
+    set_overdraw(&lines_program, bench.config.overdraw);
+
+    let mut test = Test::new(
+        "lines", gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT, bench.config.overdraw, Flags::DRAW,
+    );
+    test.primitive = gl::LINES;
+    test.vertex_count = 2 * rows;
+    let result = bench.run(&test);
+    if line_width > 1.0 && bench.config.prints_runs() {
+        println!("\tnote: wide lines may be emulated by the driver");
+    }
+
+    unsafe {
+        gl::LineWidth(1.0);
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Columns of a roughly square grid of `cells` cells.
+fn cells_columns(cells: usize) -> usize {
+    (cells as f32).sqrt().ceil() as usize
+}
+
+/// Measures the screen covered by `tris_per_draw` small triangles
+/// per instance instead of a single one, moving the bottleneck
+/// from the fill towards the vertex and primitive processing.
+fn test_triangle_grid(bench: &Benchmark, tris_per_draw: usize) -> Result<BenchmarkResult, String> {
+    let vs = compile_shader(GRID_VS_SRC, gl::VERTEX_SHADER)?;
+    let grid_program = link_program(&vs, &bench.fs)?;
+    let cells = tris_per_draw.div_ceil(2);
+    let columns = cells_columns(cells);
+    let rows = cells.div_ceil(columns);
+    unsafe {
+        gl::UseProgram(grid_program.as_raw());
+        gl::Uniform1i(uniform_location(&grid_program, "u_Columns"), columns as _);
+        gl::Uniform2f(uniform_location(&grid_program, "u_CellSize"),
+            2.0 / columns as f32, 2.0 / rows as f32);
+    }
+    set_overdraw(&grid_program, bench.config.overdraw);
+
+    let mut test = Test::new(
+        &format!("{} triangles per draw", 2 * columns * rows),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    );
+    test.vertex_count = 6 * columns * rows;
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let triangles_per_ns = (2 * columns * rows) as f64 / result.fullscreen_ns as f64;
+            println!("\ttriangle rate: {:.1} Mtri/s", triangles_per_ns * 1.0e3);
+        }
+    }
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Vertices of a roughly square grid of at least `tris_per_draw`
+/// triangles covering the screen, six per cell.
+fn grid_vertices(tris_per_draw: usize) -> Vec<Vertex> {
+    let cells = tris_per_draw.div_ceil(2);
+    let columns = cells_columns(cells);
+    let rows = cells.div_ceil(columns);
+    let corners = [(0, 0), (1, 0), (0, 1), (0, 1), (1, 0), (1, 1)];
+    let mut vertices = Vec::with_capacity(6 * columns * rows);
+    for row in 0 .. rows {
+        for column in 0 .. columns {
+            for &(dx, dy) in &corners {
+                vertices.push(Vertex {
+                    position: [
+                        2.0 * (column + dx) as f32 / columns as f32 - 1.0,
+                        2.0 * (row + dy) as f32 / rows as f32 - 1.0,
+                    ],
+                    color: [255, (column % 256) as u8, (row % 256) as u8, 255],
+                    normal: [0.0, 0.0, 1.0],
+                });
+            }
+        }
+    }
+    vertices
+}
+
+/// Points the attributes of `program` at the `Vertex` fields in
+/// the bound array buffer, recording them in the bound vertex array.
+fn set_vertex_attributes(program: &Program) {
+    let stride = std::mem::size_of::<Vertex>() as GLsizei;
+    let attributes = [
+        ("a_Position", 2, gl::FLOAT, gl::FALSE, 0),
+        ("a_Color", 4, gl::UNSIGNED_BYTE, gl::TRUE, 8),
+        ("a_Normal", 3, gl::FLOAT, gl::FALSE, 12),
+    ];
+    for &(name, size, ty, normalized, offset) in &attributes {
+        let name = std::ffi::CString::new(name).unwrap();
+        unsafe {
+            let location = gl::GetAttribLocation(program.as_raw(), name.as_ptr()) as GLuint;
+            gl::EnableVertexAttribArray(location);
+            gl::VertexAttribPointer(location, size, ty, normalized, stride, offset as *const _);
+        }
+    }
+}
+
+/// Measures the triangle grid drawn from a vertex buffer with position,
+/// color and normal attributes, next to the same grid generated in the
+/// vertex shader, to isolate the cost of fetching them.
+fn test_attribs(bench: &Benchmark) -> Result<Vec<BenchmarkResult>, String> {
+    let tris_per_draw = bench.config.tris_per_draw.unwrap_or(ATTRIBS_TRIANGLES);
+    let baseline = test_triangle_grid(bench, tris_per_draw)?;
+    let vertices = grid_vertices(tris_per_draw);
+
+    let vs = compile_shader(ATTRIBS_VS_SRC, gl::VERTEX_SHADER)?;
+    let fs = compile_shader(ATTRIBS_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let attribs_program = link_program(&vs, &fs)?;
+    let vao = Vao::new();
+    let buffer = Buffer::new();
+    unsafe {
+        gl::BindVertexArray(vao.as_raw());
+        gl::BindBuffer(gl::ARRAY_BUFFER, buffer.as_raw());
+        gl::BufferData(gl::ARRAY_BUFFER, (vertices.len() * std::mem::size_of::<Vertex>()) as _,
+            vertices.as_ptr() as *const _, gl::STATIC_DRAW);
+    }
+    set_vertex_attributes(&attribs_program);
+    unsafe {
+        gl::UseProgram(attribs_program.as_raw());
+    }
+    set_overdraw(&attribs_program, bench.config.overdraw);
+
+    // the bindings get restored even if the setup failed
+    let result = check_gl("setting up the vertex attributes").and_then(|()| {
+        let mut test = Test::new(
+            &format!("{} triangles per draw with attributes", vertices.len() / 3),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            bench.config.overdraw,
+            Flags::DRAW,
+        );
+        test.vertex_count = vertices.len();
+        bench.run(&test)
+    });
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let cost = result.fullscreen_ns as f64 - baseline.fullscreen_ns as f64;
+            println!("\tcost over '{}': {:+.3} ms", baseline.name, cost / 1.0e6);
+        }
+    }
+
+    unsafe {
+        gl::BindVertexArray(bench.vao.as_raw());
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        gl::UseProgram(bench.program.as_raw());
+    }
+    Ok(vec![baseline, result?])
+}
+
+/// Measures the triangle grid of the attribute fetch test with its
+/// vertices uploaded again before every frame's draw, which the GPU
+/// timings include. The persistent mapping cycles over `STREAM_REGIONS`
+/// regions, each guarded by a fence, and the time the CPU spends waiting
+/// on them is reported apart, relative to the `orphaned` upload.
+fn test_stream(
+    bench: &Benchmark, mode: StreamMode, orphaned: Option<&BenchmarkResult>
+) -> Result<BenchmarkResult, String> {
+    let vertices = grid_vertices(bench.config.tris_per_draw.unwrap_or(ATTRIBS_TRIANGLES));
+    let size = vertices.len() * std::mem::size_of::<Vertex>();
+    let vs = compile_shader(ATTRIBS_VS_SRC, gl::VERTEX_SHADER)?;
+    let fs = compile_shader(ATTRIBS_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let stream_program = link_program(&vs, &fs)?;
+    let vao = Vao::new();
+    let persistent_flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+    let buffer = Buffer::new();
+    let mut mapping = std::ptr::null_mut();
+    unsafe {
+        gl::BindVertexArray(vao.as_raw());
+        gl::BindBuffer(gl::ARRAY_BUFFER, buffer.as_raw());
+        if mode == StreamMode::Persistent {
+            let total = (size * STREAM_REGIONS) as GLsizeiptr;
+            gl::BufferStorage(gl::ARRAY_BUFFER, total, std::ptr::null(), persistent_flags);
+            mapping = gl::MapBufferRange(gl::ARRAY_BUFFER, 0, total, persistent_flags);
+        } else {
+            gl::BufferData(gl::ARRAY_BUFFER, size as _, std::ptr::null(), gl::STREAM_DRAW);
+        }
+    }
+    check_gl("creating the streamed vertex buffer")?;
+    set_vertex_attributes(&stream_program);
+    unsafe {
+        gl::UseProgram(stream_program.as_raw());
+    }
+
+    let count = vertices.len();
+    let fences: Rc<RefCell<[GLsync; STREAM_REGIONS]>> =
+        Rc::new(RefCell::new([std::ptr::null(); STREAM_REGIONS]));
+    let fence_wait = Rc::new(Cell::new(Duration::new(0, 0)));
+    let frames = Rc::new(Cell::new(0usize));
+    let (work_fences, work_fence_wait, work_frames) =
+        (Rc::clone(&fences), Rc::clone(&fence_wait), Rc::clone(&frames));
+    let mut test = Test::new(
+        &format!("{} triangles streamed by {}", count / 3, mode),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        1,
+        Flags::DRAW,
+    );
+    test.vertex_count = count;
+    test.work = Some(Box::new(move || unsafe {
+        let data = vertices.as_ptr() as *const u8;
+        let region = work_frames.get() % STREAM_REGIONS;
+        work_frames.set(work_frames.get() + 1);
+        let mut first = 0;
+        match mode {
+            StreamMode::Orphan => {
+                gl::BufferData(gl::ARRAY_BUFFER, size as _, std::ptr::null(), gl::STREAM_DRAW);
+                gl::BufferSubData(gl::ARRAY_BUFFER, 0, size as _, data as *const _);
+            }
+            StreamMode::Map => {
+                let access = gl::MAP_WRITE_BIT | gl::MAP_INVALIDATE_BUFFER_BIT;
+                let mapping = gl::MapBufferRange(gl::ARRAY_BUFFER, 0, size as _, access);
+                std::ptr::copy_nonoverlapping(data, mapping as *mut u8, size);
+                gl::UnmapBuffer(gl::ARRAY_BUFFER);
+            }
+            StreamMode::Persistent => {
+                // the region was last drawn `STREAM_REGIONS` frames ago
+                let previous = std::mem::replace(&mut work_fences.borrow_mut()[region], std::ptr::null());
+                if !previous.is_null() {
+                    let start = Instant::now();
+                    gl::ClientWaitSync(previous, gl::SYNC_FLUSH_COMMANDS_BIT, u64::MAX);
+                    work_fence_wait.set(work_fence_wait.get() + start.elapsed());
+                    gl::DeleteSync(previous);
+                }
+                std::ptr::copy_nonoverlapping(data, (mapping as *mut u8).add(region * size), size);
+                first = region * count;
+            }
+        }
+        gl::DrawArrays(gl::TRIANGLES, first as _, count as _);
+        if mode == StreamMode::Persistent {
+            work_fences.borrow_mut()[region] = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+        }
+    }));
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            println!("\tupload rate: {:.0} MB/s", size as f64 / result.fullscreen_ns as f64 * 1.0e3);
+            if mode == StreamMode::Persistent {
+                let wait_ns = fence_wait.get().as_nanos() as f64 / frames.get().max(1) as f64;
+                println!("\tfence wait: {:.3} ms per frame", wait_ns / 1.0e6);
+            }
+            if let Some(orphaned) = orphaned {
+                println!("\tcost over '{}': {:+.3} ms", orphaned.name,
+                    (result.fullscreen_ns as f64 - orphaned.fullscreen_ns as f64) / 1.0e6);
+            }
+        }
+    }
+
+    unsafe {
+        if mode == StreamMode::Persistent {
+            for &fence in fences.borrow().iter() {
+                gl::DeleteSync(fence);
+            }
+            gl::UnmapBuffer(gl::ARRAY_BUFFER);
+        }
+        gl::BindVertexArray(bench.vao.as_raw());
+        gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Measures `blend_layers` full-screen triangles alpha-blended
+/// on top of each other. Depth testing is disabled so that all
+/// the layers reach the blender.
+fn test_blend(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let fs = compile_shader(BLEND_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let blend_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(blend_program.as_raw());
+        gl::Disable(gl::DEPTH_TEST);
+        gl::Enable(gl::BLEND);
+        gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+    }
+
+    let result = bench.run(&Test::new(
+        "alpha blended", gl::COLOR_BUFFER_BIT, bench.config.blend_layers, Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::Disable(gl::BLEND);
+        gl::Enable(gl::DEPTH_TEST);
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+fn internal_format(format: ColorFormat) -> GLenum {
+    match format {
+        ColorFormat::Rgba8 => gl::RGBA8,
+        ColorFormat::Rgba16f => gl::RGBA16F,
+        ColorFormat::R11fG11fB10f => gl::R11F_G11F_B10F,
+    }
+}
+
+fn depth_func(func: DepthFunc) -> GLenum {
+    match func {
+        DepthFunc::Never => gl::NEVER,
+        DepthFunc::Less => gl::LESS,
+        DepthFunc::Equal => gl::EQUAL,
+        DepthFunc::Lequal => gl::LEQUAL,
+        DepthFunc::Greater => gl::GREATER,
+        DepthFunc::Notequal => gl::NOTEQUAL,
+        DepthFunc::Gequal => gl::GEQUAL,
+        DepthFunc::Always => gl::ALWAYS,
+    }
+}
+
+/// RGBA8 texels of a noisy pattern, in rows of `width`, so
+/// that neighboring texels differ.
+fn noise_texels(width: u32, count: u32) -> Vec<u32> {
+    (0 .. count)
+        .map(|i| (i % width) ^ (i / width) ^ i.wrapping_mul(0x9E37_79B9))
+        .collect()
+}
+
+fn gl_filter(filter: Filter) -> GLenum {
+    match filter {
+        Filter::Nearest => gl::NEAREST,
+        Filter::Linear => gl::LINEAR,
+    }
+}
+
+/// Creates a square texture of the given format filled with
+/// a noisy pattern.
+fn create_texture(size: u32, filter: Filter, format: ColorFormat) -> GLuint {
+    let texels = noise_texels(size, size * size);
+    let filter = gl_filter(filter);
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexImage2D(gl::TEXTURE_2D, 0, internal_format(format) as _, size as _, size as _, 0,
+            gl::RGBA, gl::UNSIGNED_BYTE, texels.as_ptr() as *const _);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter as _);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter as _);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as _);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as _);
+    }
+    texture
+}
+
+/// Measures a full-screen triangle sampling a `texture_size`
+/// texture for every pixel.
+fn test_texture(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    let texture = create_texture(config.texture_size, config.texture_filter, config.color_format);
+    check_gl("creating the texture")?;
+    let fs = compile_shader(TEXTURE_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let texture_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(texture_program.as_raw());
+        gl::Uniform1i(uniform_location(&texture_program, "u_Texture"), 0);
+    }
+    set_overdraw(&texture_program, config.overdraw);
+
+    let name = format!("texture {0}x{0} {1} {2}",
+        config.texture_size, config.texture_filter, config.color_format);
+    let result = bench.run(&Test::new(
+        &name, gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT, config.overdraw, Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    result
+}
+
+/// Measures a full-screen triangle sampling a mipmapped `texture_size`
+/// texture at a grazing angle, once per anisotropy level. The levels
+/// above the driver's maximum are clamped to it.
+fn test_aniso(bench: &Benchmark, levels: &[u32]) -> Result<Vec<BenchmarkResult>, String> {
+    if !supports_gl(4, 6) && !has_extension("GL_EXT_texture_filter_anisotropic") &&
+        !has_extension("GL_ARB_texture_filter_anisotropic") {
+        eprintln!("warning: anisotropic filtering is not supported, skipping its test");
+        return Ok(Vec::new());
+    }
+    let config = bench.config;
+    let mut max_level = 0.0;
+    unsafe { gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY_EXT, &mut max_level) };
+    let texture = create_texture(config.texture_size, Filter::Linear, config.color_format);
+    unsafe {
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as _);
+    }
+    check_gl("creating the mipmapped texture")?;
+    let fs = compile_shader(ANISO_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let aniso_program = link_program(&bench.vs, &fs)?;
+    let (width, height) = bench.size();
+    unsafe {
+        gl::UseProgram(aniso_program.as_raw());
+        gl::Uniform1i(uniform_location(&aniso_program, "u_Texture"), 0);
+        gl::Uniform2f(uniform_location(&aniso_program, "u_Resolution"), width as f32, height as f32);
+    }
+    set_overdraw(&aniso_program, config.overdraw);
+    if config.prints_runs() {
+        println!("Maximum anisotropy: {}x", max_level);
+    }
+
+    let results = levels
+        .iter()
+        .map(|&level| {
+            let level = if level as f32 > max_level {
+                eprintln!("warning: {}x anisotropy is over the maximum, clamping it to {}x",
+                    level, max_level);
+                max_level
+            } else {
+                level as f32
+            };
+            unsafe { gl::TexParameterf(gl::TEXTURE_2D, TEXTURE_MAX_ANISOTROPY_EXT, level) };
+            bench.run(&Test::new(
+                &format!("anisotropic {}x, texture {1}x{1} {2}",
+                    level, config.texture_size, config.color_format),
+                gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+                config.overdraw,
+                Flags::DRAW,
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>();
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    results
+}
+
+/// Measures a full-screen triangle sampling a cubemap with `size`
+/// faces along reflection vectors, with seamless filtering.
+fn test_cubemap(bench: &Benchmark, size: u32) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    let filter = gl_filter(config.texture_filter);
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, texture);
+        for face in 0 .. 6 {
+            let texels = noise_texels(size, size * size)
+                .into_iter()
+                .map(|texel| texel.rotate_left(face * 5))
+                .collect::<Vec<_>>();
+            gl::TexImage2D(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face, 0,
+                internal_format(config.color_format) as _, size as _, size as _, 0,
+                gl::RGBA, gl::UNSIGNED_BYTE, texels.as_ptr() as *const _);
+        }
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MIN_FILTER, filter as _);
+        gl::TexParameteri(gl::TEXTURE_CUBE_MAP, gl::TEXTURE_MAG_FILTER, filter as _);
+        // always on with GLES
+        if !is_gles() {
+            gl::Enable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+        }
+    }
+    check_gl("creating the cubemap")?;
+    let fs = compile_shader(CUBEMAP_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let cubemap_program = link_program(&bench.vs, &fs)?;
+    let (width, height) = bench.size();
+    unsafe {
+        gl::UseProgram(cubemap_program.as_raw());
+        gl::Uniform1i(uniform_location(&cubemap_program, "u_Cubemap"), 0);
+        gl::Uniform2f(uniform_location(&cubemap_program, "u_Resolution"), width as f32, height as f32);
+    }
+    set_overdraw(&cubemap_program, config.overdraw);
+
+    let result = bench.run(&Test::new(
+        &format!("cubemap {0}x{0} {1} {2}", size, config.texture_filter, config.color_format),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        config.overdraw,
+        Flags::DRAW,
+    ));
+    if result.is_ok() && config.prints_runs() {
+        println!("\tface size: {0}x{0}", size);
+    }
+
+    unsafe {
+        if !is_gles() {
+            gl::Disable(gl::TEXTURE_CUBE_MAP_SEAMLESS);
+        }
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    result
+}
+
+/// Measures a full-screen triangle sampling a `size` cubed 3D texture,
+/// across its slices, with the configured filter.
+fn test_texture_3d(bench: &Benchmark, size: u32) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    let mut max_size = 0;
+    unsafe { gl::GetIntegerv(gl::MAX_3D_TEXTURE_SIZE, &mut max_size) };
+    if size > max_size as u32 {
+        return Err(format!("3D texture size {} is over the maximum of {}", size, max_size));
+    }
+    let count = size
+        .checked_pow(3)
+        .ok_or_else(|| format!("3D texture size {} has too many texels", size))?;
+    let texels = noise_texels(size, count);
+    let filter = gl_filter(config.texture_filter);
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_3D, texture);
+        gl::TexImage3D(gl::TEXTURE_3D, 0, internal_format(config.color_format) as _,
+            size as _, size as _, size as _, 0,
+            gl::RGBA, gl::UNSIGNED_BYTE, texels.as_ptr() as *const _);
+        gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_MIN_FILTER, filter as _);
+        gl::TexParameteri(gl::TEXTURE_3D, gl::TEXTURE_MAG_FILTER, filter as _);
+        for &wrap in &[gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+            gl::TexParameteri(gl::TEXTURE_3D, wrap, gl::REPEAT as _);
+        }
+    }
+    check_gl("creating the 3D texture")?;
+    let fs = compile_shader(TEXTURE_3D_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let texture_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(texture_program.as_raw());
+        gl::Uniform1i(uniform_location(&texture_program, "u_Texture"), 0);
+    }
+    set_overdraw(&texture_program, config.overdraw);
+
+    let filter_name = match config.texture_filter {
+        Filter::Nearest => "nearest",
+        Filter::Linear => "trilinear",
+    };
+    let result = bench.run(&Test::new(
+        &format!("3D texture {0}x{0}x{0} {1} {2}", size, filter_name, config.color_format),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        config.overdraw,
+        Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindTexture(gl::TEXTURE_3D, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    result
+}
+
+/// Measures a full-screen triangle sampling an array of `layers`
+/// textures of `texture_size`, comparing it with the plain texture
+/// test when it ran.
+fn test_texture_array(
+    bench: &Benchmark, layers: u32, plain: Option<&BenchmarkResult>,
+) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    let mut max_layers = 0;
+    unsafe { gl::GetIntegerv(gl::MAX_ARRAY_TEXTURE_LAYERS, &mut max_layers) };
+    if layers > max_layers as u32 {
+        return Err(format!("{} array texture layers are over the maximum of {}", layers, max_layers));
+    }
+    let size = config.texture_size;
+    let count = (size * size)
+        .checked_mul(layers)
+        .ok_or_else(|| format!("{} layers of {1}x{1} have too many texels", layers, size))?;
+    let texels = noise_texels(size, count);
+    let filter = gl_filter(config.texture_filter);
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, texture);
+        gl::TexImage3D(gl::TEXTURE_2D_ARRAY, 0, internal_format(config.color_format) as _,
+            size as _, size as _, layers as _, 0,
+            gl::RGBA, gl::UNSIGNED_BYTE, texels.as_ptr() as *const _);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MIN_FILTER, filter as _);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_MAG_FILTER, filter as _);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_S, gl::REPEAT as _);
+        gl::TexParameteri(gl::TEXTURE_2D_ARRAY, gl::TEXTURE_WRAP_T, gl::REPEAT as _);
+    }
+    check_gl("creating the array texture")?;
+    let fs = compile_shader(TEXTURE_ARRAY_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let texture_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(texture_program.as_raw());
+        gl::Uniform1i(uniform_location(&texture_program, "u_Texture"), 0);
+    }
+    set_overdraw(&texture_program, config.overdraw);
+
+    let result = bench.run(&Test::new(
+        &format!("texture array {0}x{0}, {1} layers {2} {3}",
+            size, layers, config.texture_filter, config.color_format),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        config.overdraw,
+        Flags::DRAW,
+    ));
+    if let Ok(ref result) = result {
+        if config.prints_runs() {
+            println!("\tlayer count: {}", layers);
+            if let Some(plain) = plain {
+                println!("\tmega-pixel time over '{}': {:.2}x", plain.name,
+                    result.megapixel_ns as f64 / plain.megapixel_ns as f64);
+            }
+        }
+    }
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindTexture(gl::TEXTURE_2D_ARRAY, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    result
+}
+
+/// Measures a full-screen triangle rendered into a framebuffer
+/// with `mrt` color attachments.
+fn test_mrt(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let count = bench.config.mrt;
+    let (width, height) = bench.size();
+    let format = bench.config.color_format;
+    let fs = compile_shader(&mrt_fs_src(count), gl::FRAGMENT_SHADER)?;
+    let mrt_program = link_program(&bench.vs, &fs)?;
+    let framebuffer = Framebuffer::new(width, height, count, internal_format(format))?;
+    unsafe {
+        gl::UseProgram(mrt_program.as_raw());
+    }
+    set_overdraw(&mrt_program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        &format!("{} {} render targets", count, format),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+    }
+    drop(framebuffer);
+    bench.bind_target();
+    result
+}
+
+/// Checks that a few pixels spread over the bound framebuffer,
+/// of the given size, all have the `expected` RGBA8 color.
+fn pixels_match(width: u32, height: u32, expected: [u8; 4]) -> bool {
+    let (x1, y1, x2, y2) = (1, 1, width as GLint - 2, height as GLint - 2);
+    let points = [(x1, y1), (x2, y1), (width as GLint / 2, height as GLint / 2), (x1, y2), (x2, y2)];
+    points.iter().all(|&(x, y)| {
+        let mut pixel = [0u8; 4];
+        unsafe {
+            gl::ReadPixels(x, y, 1, 1, gl::RGBA, gl::UNSIGNED_BYTE, pixel.as_mut_ptr() as *mut _);
+        }
+        pixel == expected
+    })
+}
+
+/// Draws one more frame like the color and depth test, over the clear
+/// color of the rejection tests, and checks the drawn color made it
+/// to the render target before presenting it. The depth test always
+/// passes, whatever the configured function and clear depth.
+fn verify_output(bench: &Benchmark) -> Result<bool, String> {
+    let (width, height) = bench.size();
+    unsafe {
+        gl::ClearColor(1.0, 0.3, 0.3, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        gl::DepthFunc(gl::ALWAYS);
+        gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, 1);
+        gl::DepthFunc(depth_func(bench.config.depth_func));
+    }
+    let matches = pixels_match(width, height, [255; 4]);
+    check_gl("verifying the output")?;
+    bench.context.swap_buffers()?;
+    Ok(matches)
+}
+
+/// Measures the color and depth test rendered into a texture, then
+/// reads it back to make sure that the draws actually produced pixels.
+fn test_render_to_texture(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let (width, height) = bench.size();
+    let framebuffer = Framebuffer::new(width, height, 1, internal_format(bench.config.color_format))?;
+    set_overdraw(&bench.program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        "color and depth to texture",
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    ));
+    if result.is_ok() && !pixels_match(width, height, [255; 4]) {
+        eprintln!("warning: the texture doesn't contain the drawn color, \
+            the draws may have been skipped");
+    }
+
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    drop(framebuffer);
+    bench.bind_target();
+    result
+}
+
+/// Checks whether the render target encodes the colors it's written as sRGB.
+fn target_is_srgb(bench: &Benchmark) -> bool {
+    let attachment = if bench.target_fbo.get() != 0 {
+        gl::COLOR_ATTACHMENT0
+    } else if is_gles() {
+        gl::BACK
+    } else {
+        gl::BACK_LEFT
+    };
+    let mut encoding = 0;
+    unsafe {
+        gl::GetFramebufferAttachmentParameteriv(gl::FRAMEBUFFER, attachment,
+            gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING, &mut encoding);
+    }
+    encoding as GLenum == gl::SRGB
+}
+
+/// Measures the color and depth test with `GL_FRAMEBUFFER_SRGB` enabled,
+/// so that every written pixel goes through the sRGB encode. GLES has no
+/// switch for it, and encodes into sRGB targets in all the tests.
+fn test_srgb(bench: &Benchmark, baseline: &BenchmarkResult) -> Result<BenchmarkResult, String> {
+    let encodes = target_is_srgb(bench);
+    if !encodes {
+        eprintln!("warning: the render target isn't sRGB-capable, \
+            the sRGB test measures the linear writes");
+    }
+    unsafe {
+        if !is_gles() {
+            gl::Enable(gl::FRAMEBUFFER_SRGB);
+        }
+    }
+    set_overdraw(&bench.program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        "color and depth sRGB",
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    ));
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            if encodes {
+                println!("\tsRGB encode active");
+            }
+            let cost = result.fullscreen_ns as f64 - baseline.fullscreen_ns as f64;
+            println!("\tcost over '{}': {:+.3} ms", baseline.name, cost / 1.0e6);
+        }
+    }
+
+    unsafe {
+        if !is_gles() {
+            gl::Disable(gl::FRAMEBUFFER_SRGB);
+        }
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    result
+}
+
+/// Measures the color and depth test rendered into an RGBA16F
+/// framebuffer the size of the render target, the common format
+/// of HDR pipelines.
+fn test_hdr(bench: &Benchmark, baseline: &BenchmarkResult) -> Result<BenchmarkResult, String> {
+    let (width, height) = bench.size();
+    let (fbo, size) = (bench.target_fbo.get(), bench.target_size.get());
+    let framebuffer = Framebuffer::new(width, height, 1, gl::RGBA16F)?;
+    bench.set_target(framebuffer.fbo, Some((width, height)));
+    set_overdraw(&bench.program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        "color and depth rgba16f",
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    ));
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            println!("\tmega-pixel time over '{}': {:.2}x", baseline.name,
+                result.megapixel_ns as f64 / baseline.megapixel_ns as f64);
+        }
+    }
+
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    drop(framebuffer);
+    bench.set_target(fbo, size);
+    result
+}
+
+/// Measures the color and depth test with the polygons rasterized
+/// as their outlines. Desktop GL only, GLES has no polygon modes.
+fn test_wireframe(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    unsafe {
+        gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+    }
+    set_overdraw(&bench.program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        "color and depth wireframe",
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    result
+}
+
+/// Measures a front-facing and a back-facing full-screen triangle per
+/// instance, first with all of them shaded, then with the back faces
+/// culled. Depth testing is disabled so that culling is the only thing
+/// keeping the back faces from being shaded.
+fn test_cull(bench: &Benchmark) -> Result<Vec<BenchmarkResult>, String> {
+    let vs = compile_shader(CULL_VS_SRC, gl::VERTEX_SHADER)?;
+    let cull_program = link_program(&vs, &bench.fs)?;
+    unsafe {
+        gl::UseProgram(cull_program.as_raw());
+        gl::Disable(gl::DEPTH_TEST);
+        gl::FrontFace(gl::CCW);
+        gl::CullFace(gl::BACK);
+    }
+
+    let new_test = |name: &str| {
+        let mut test = Test::new(name, gl::COLOR_BUFFER_BIT, bench.config.overdraw, Flags::DRAW);
+        test.vertex_count = 6;
+        test
+    };
+    let unculled = bench.run(&new_test("front and back faces"));
+    unsafe {
+        gl::Enable(gl::CULL_FACE);
+    }
+    let culled = bench.run(&new_test("front and back faces culled"));
+    if let (Ok(unculled), Ok(culled)) = (&unculled, &culled) {
+        if bench.config.prints_runs() {
+            let saved = 1.0 - culled.fullscreen_ns as f64 / unculled.fullscreen_ns as f64;
+            println!("\tculling saved {:.0}% of '{}'", saved * 100.0, unculled.name);
+        }
+    }
+
+    unsafe {
+        gl::Disable(gl::CULL_FACE);
+        gl::Enable(gl::DEPTH_TEST);
+        gl::UseProgram(bench.program.as_raw());
+    }
+    Ok(vec![unculled?, culled?])
+}
+
+/// Measures the color and depth test with the color writes masked
+/// off and an empty fragment shader, leaving only the depth writes.
+fn test_depth_only(bench: &Benchmark, baseline: &BenchmarkResult) -> Result<BenchmarkResult, String> {
+    let fs = compile_shader(DEPTH_ONLY_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let depth_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(depth_program.as_raw());
+        gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+    }
+    set_overdraw(&depth_program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        "depth only", gl::DEPTH_BUFFER_BIT, bench.config.overdraw, Flags::DRAW,
+    ));
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            println!("\tmega-pixel time over '{}': {:.2}x", baseline.name,
+                result.megapixel_ns as f64 / baseline.megapixel_ns as f64);
+        }
+    }
+
+    unsafe {
+        gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Measures the color and depth test with the fragment shader writing
+/// to a storage image the size of the target instead, bypassing the
+/// blending hardware, with the color writes masked off.
+fn test_image_store(bench: &Benchmark, baseline: &BenchmarkResult) -> Result<BenchmarkResult, String> {
+    let format = bench.config.color_format;
+    let (width, height) = bench.size();
+    let fs = compile_shader(&image_store_fs_src(format), gl::FRAGMENT_SHADER)?;
+    let image_program = link_program(&bench.vs, &fs)?;
+    let mut image = 0;
+    unsafe {
+        gl::GenTextures(1, &mut image);
+        gl::BindTexture(gl::TEXTURE_2D, image);
+        gl::TexStorage2D(gl::TEXTURE_2D, 1, internal_format(format), width as _, height as _);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gl::BindImageTexture(0, image, 0, gl::FALSE, 0, gl::WRITE_ONLY, internal_format(format));
+        gl::UseProgram(image_program.as_raw());
+        gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+    }
+    check_gl("creating the storage image")?;
+    set_overdraw(&image_program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        &format!("image store {}", format), gl::DEPTH_BUFFER_BIT, bench.config.overdraw, Flags::DRAW,
+    ));
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            println!("\timage format: {}", format);
+            println!("\tmega-pixel time over '{}': {:.2}x", baseline.name,
+                result.megapixel_ns as f64 / baseline.megapixel_ns as f64);
+        }
+    }
+
+    unsafe {
+        gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindImageTexture(0, 0, 0, gl::FALSE, 0, gl::WRITE_ONLY, gl::RGBA8);
+        gl::DeleteTextures(1, &image);
+    }
+    result
+}
+
+/// Measures `num_rejects` full-screen triangles, with `stencil_reject`
+/// of the screen failing the stencil test.
+fn test_stencil(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let (width, height) = bench.size();
+    let fraction = bench.config.stencil_reject;
+    let rejected_rows = (height as f32 * fraction).round() as GLsizei;
+    unsafe {
+        gl::Disable(gl::DEPTH_TEST);
+        gl::Enable(gl::STENCIL_TEST);
+        gl::StencilFunc(gl::EQUAL, 0, !0);
+        gl::StencilOp(gl::KEEP, gl::KEEP, gl::KEEP);
+    }
+
+    let mut test = Test::new(
+        &format!("stencil rejected {}%", fraction * 100.0),
+        gl::COLOR_BUFFER_BIT,
+        bench.config.num_rejects,
+        Flags::DRAW,
+    );
+    // mark the rejected rows at the bottom of the screen with 1
+    test.prepare = Some(Box::new(move || unsafe {
+        gl::ClearStencil(0);
+        gl::Clear(gl::STENCIL_BUFFER_BIT);
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::Scissor(0, 0, width as _, rejected_rows);
+        gl::ClearStencil(1);
+        gl::Clear(gl::STENCIL_BUFFER_BIT);
+        gl::Disable(gl::SCISSOR_TEST);
+    }));
+    let result = bench.run(&test);
+
+    unsafe {
+        gl::Disable(gl::STENCIL_TEST);
+        gl::Enable(gl::DEPTH_TEST);
+    }
+    result
+}
+
+/// Measures `num_rejects` full-screen triangles with a fragment
+/// shader discarding the given fraction of the fragments.
+fn test_discard(bench: &Benchmark, fraction: f32) -> Result<BenchmarkResult, String> {
+    let fs = compile_shader(DISCARD_FS_SRC, gl::FRAGMENT_SHADER)?;
+    let discard_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(discard_program.as_raw());
+        gl::Uniform1f(uniform_location(&discard_program, "u_Fraction"), fraction);
+    }
+
+    let result = bench.run(&Test::new(
+        &format!("discarded {}%", fraction * 100.0),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.num_rejects,
+        Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Measures a full-screen triangle with `ops` multiply-adds
+/// in its fragment shader.
+fn test_alu(bench: &Benchmark, ops: usize) -> Result<BenchmarkResult, String> {
+    let fs = compile_shader(&alu_fs_src(ops), gl::FRAGMENT_SHADER)?;
+    let alu_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(alu_program.as_raw());
+        gl::Uniform1f(uniform_location(&alu_program, "u_Scale"), 0.999);
+        gl::Uniform1f(uniform_location(&alu_program, "u_Bias"), 1.0e-4);
+    }
+    set_overdraw(&alu_program, bench.config.overdraw);
+
+    let result = bench.run(&Test::new(
+        &format!("alu {} ops", ops),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        bench.config.overdraw,
+        Flags::DRAW,
+    ));
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+    }
+    result
+}
+
+/// Measures a compute shader writing every pixel of a storage
+/// image the size of the window. Requires GL 4.3.
+fn test_compute(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let (width, height) = bench.size();
+    let cs = compile_shader(COMPUTE_SRC, gl::COMPUTE_SHADER)?;
+    let compute_program = link_shaders(&[&cs])?;
+    let mut image = 0;
+    unsafe {
+        gl::GenTextures(1, &mut image);
+        gl::BindTexture(gl::TEXTURE_2D, image);
+        gl::TexStorage2D(gl::TEXTURE_2D, 1, gl::RGBA8, width as _, height as _);
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gl::BindImageTexture(0, image, 0, gl::FALSE, 0, gl::WRITE_ONLY, gl::RGBA8);
+        gl::UseProgram(compute_program.as_raw());
+    }
+    check_gl("creating the storage image")?;
+
+    let groups_x = width.div_ceil(COMPUTE_GROUP_SIZE);
+    let groups_y = height.div_ceil(COMPUTE_GROUP_SIZE);
+    let mut test = Test::new("compute image store", 0, 1, Flags::DRAW);
+    test.work = Some(Box::new(move || unsafe {
+        gl::DispatchCompute(groups_x, groups_y, 1);
+    }));
+    let result = bench.run(&test);
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::DeleteTextures(1, &image);
+    }
+    result
+}
+
+/// Parameters of an indirect draw, as laid out in the indirect buffer.
+#[repr(C)]
+struct DrawArraysCommand {
+    count: GLuint,
+    instance_count: GLuint,
+    first: GLuint,
+    base_instance: GLuint,
+}
+
+/// Creates an indirect buffer holding the given draws, and leaves it bound.
+fn create_indirect_buffer(commands: &[DrawArraysCommand]) -> Result<GLuint, String> {
+    let mut buffer = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut buffer);
+        gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, buffer);
+        gl::BufferData(gl::DRAW_INDIRECT_BUFFER, std::mem::size_of_val(commands) as _,
+            commands.as_ptr() as *const _, gl::STATIC_DRAW);
+    }
+    check_gl("creating the indirect buffer")?;
+    Ok(buffer)
+}
+
+/// Measures the color and depth test with its instances drawn by
+/// `glDrawArraysIndirect` instead of `glDrawArraysInstanced`.
+fn test_indirect(bench: &Benchmark, baseline: &BenchmarkResult) -> Result<BenchmarkResult, String> {
+    let num_draws = bench.config.overdraw;
+    let buffer = create_indirect_buffer(&[DrawArraysCommand {
+        count: 3,
+        instance_count: num_draws as _,
+        first: 0,
+        base_instance: 0,
+    }])?;
+    set_overdraw(&bench.program, num_draws);
+
+    let mut test = Test::new(
+        "color and depth indirect", gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT, num_draws, Flags::DRAW,
+    );
+    test.work = Some(Box::new(|| unsafe {
+        gl::DrawArraysIndirect(gl::TRIANGLES, std::ptr::null());
+    }));
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let delta = result.fullscreen_ns as f64 - baseline.fullscreen_ns as f64;
+            println!("\tcost over '{}': {:+.3} ms", baseline.name, delta / 1.0e6);
+        }
+    }
+
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+        gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0);
+        gl::DeleteBuffers(1, &buffer);
+    }
+    result
+}
+
+/// Measures the submission of `count` draws rasterizing nothing, first
+/// with a `glDrawArrays` call each, then in a single
+/// `glMultiDrawArraysIndirect`. Requires GL 4.3.
+fn test_multi_draw(bench: &Benchmark, count: usize) -> Result<Vec<BenchmarkResult>, String> {
+    let vs = compile_shader(EMPTY_VS_SRC, gl::VERTEX_SHADER)?;
+    let empty_program = link_program(&vs, &bench.fs)?;
+    let commands = (0 .. count)
+        .map(|_| DrawArraysCommand { count: 3, instance_count: 1, first: 0, base_instance: 0 })
+        .collect::<Vec<_>>();
+    let buffer = create_indirect_buffer(&commands)?;
+    unsafe {
+        gl::UseProgram(empty_program.as_raw());
+    }
+
+    let mut direct = Test::new(&format!("{} direct draws", count), 0, count, Flags::DRAW);
+    direct.work = Some(Box::new(move || unsafe {
+        for _ in 0 .. count {
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }));
+    let mut multi = Test::new(&format!("{} multi-draw indirect draws", count), 0, count, Flags::DRAW);
+    multi.work = Some(Box::new(move || unsafe {
+        gl::MultiDrawArraysIndirect(gl::TRIANGLES, std::ptr::null(), count as _, 0);
+    }));
+    let results = [direct, multi]
+        .iter()
+        .map(|test| bench.run(test))
+        .collect::<Result<Vec<_>, _>>();
+    if let Ok(ref results) = results {
+        if bench.config.prints_runs() {
+            println!("Draw submission:");
+            for result in results {
+                println!("\t{}: {:.1} M draws per second",
+                    result.name, 1.0e3 / result.fullscreen_ns.max(1) as f64);
+            }
+        }
+    }
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, 0);
+        gl::DeleteBuffers(1, &buffer);
+    }
+    results
+}
+
+/// Measures a vertex shader run on `count` points per frame, with its
+/// outputs captured into a buffer by transform feedback and nothing
+/// rasterized.
+fn test_transform_feedback(bench: &Benchmark, count: usize) -> Result<BenchmarkResult, String> {
+    let vs = compile_shader(FEEDBACK_VS_SRC, gl::VERTEX_SHADER)?;
+    let feedback_program = link_feedback_program(&[&vs, &bench.fs], &["v_Position", "v_Velocity"])?;
+    let size = count * FEEDBACK_VERTEX_SIZE;
+    let mut buffer = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut buffer);
+        gl::BindBuffer(gl::TRANSFORM_FEEDBACK_BUFFER, buffer);
+        gl::BufferData(gl::TRANSFORM_FEEDBACK_BUFFER, size as _, std::ptr::null(), gl::DYNAMIC_COPY);
+        gl::BindBufferBase(gl::TRANSFORM_FEEDBACK_BUFFER, 0, buffer);
+        gl::UseProgram(feedback_program.as_raw());
+        gl::Enable(gl::RASTERIZER_DISCARD);
+    }
+    check_gl("creating the transform feedback buffer")?;
+    if bench.config.prints_runs() {
+        println!("Transform feedback buffer: {:.1} MB", size as f64 / 1.0e6);
+    }
+
+    let mut test = Test::new(&format!("transform feedback {} vertices", count), 0, 1, Flags::DRAW);
+    test.work = Some(Box::new(move || unsafe {
+        gl::BeginTransformFeedback(gl::POINTS);
+        gl::DrawArrays(gl::POINTS, 0, count as _);
+        gl::EndTransformFeedback();
+    }));
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            println!("\tprimitives per second: {:.1} M",
+                count as f64 / result.fullscreen_ns as f64 * 1.0e3);
+        }
+    }
+
+    unsafe {
+        gl::Disable(gl::RASTERIZER_DISCARD);
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindBufferBase(gl::TRANSFORM_FEEDBACK_BUFFER, 0, 0);
+        gl::BindBuffer(gl::TRANSFORM_FEEDBACK_BUFFER, 0);
+        gl::DeleteBuffers(1, &buffer);
+    }
+    result
+}
+
+/// Measures a compute shader reading, writing, or both, every vec4
+/// of a storage buffer of `ssbo_size` per frame. Requires GL 4.3.
+fn test_ssbo(bench: &Benchmark, access: SsboAccess) -> Result<BenchmarkResult, String> {
+    let size = bench.config.ssbo_size << 20;
+    let cs = compile_shader(&ssbo_cs_src(access), gl::COMPUTE_SHADER)?;
+    let ssbo_program = link_shaders(&[&cs])?;
+    let contents = vec![0u8; size];
+    let mut buffer = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut buffer);
+        gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, buffer);
+        gl::BufferData(gl::SHADER_STORAGE_BUFFER, size as _, contents.as_ptr() as *const _,
+            gl::DYNAMIC_COPY);
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, buffer);
+        gl::UseProgram(ssbo_program.as_raw());
+        gl::Uniform1ui(uniform_location(&ssbo_program, "u_Count"), (size / 16) as _);
+    }
+    check_gl("creating the storage buffer")?;
+
+    let mut test = Test::new(
+        &format!("storage buffer {} {} MiB", access, bench.config.ssbo_size), 0, 1, Flags::DRAW,
+    );
+    test.work = Some(Box::new(|| unsafe {
+        gl::DispatchCompute(SSBO_GROUPS, 1, 1);
+        gl::MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+    }));
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let bytes = (size * access.passes()) as f64;
+            println!("\tbandwidth: {:.1} GB/s", bytes / result.fullscreen_ns as f64);
+        }
+    }
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, 0);
+        gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
+        gl::DeleteBuffers(1, &buffer);
+    }
+    result
+}
+
+/// Measures the color and depth test rendering into a framebuffer
+/// of each of the given sizes.
+fn test_resolution_sweep(bench: &Benchmark, sizes: &[Resolution]) -> Result<Vec<BenchmarkResult>, String> {
+    let (fbo, size) = (bench.target_fbo.get(), bench.target_size.get());
+    set_overdraw(&bench.program, bench.config.overdraw);
+    let mut results = Vec::with_capacity(sizes.len());
+    for &resolution in sizes {
+        let framebuffer = Framebuffer::new(resolution.width, resolution.height, 1,
+            internal_format(bench.config.color_format))?;
+        bench.set_target(framebuffer.fbo, Some((resolution.width, resolution.height)));
+        let result = bench.run(&Test::new(
+            &format!("color and depth {}", resolution),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            bench.config.overdraw,
+            Flags::DRAW,
+        ));
+        drop(framebuffer);
+        bench.set_target(fbo, size);
+        results.push(result?);
+    }
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+
+    if bench.config.prints_runs() {
+        println!("Resolution sweep:");
+        println!("| resolution | full-screen time | mega-pixel time |");
+        for (resolution, result) in sizes.iter().zip(&results) {
+            println!("| {} | {:.2} ms | {} mcs |", resolution,
+                result.fullscreen_ns as f32 / 1.0e6, result.megapixel_ns / 1000);
+        }
+    }
+    Ok(results)
+}
+
+/// Measures the color and depth test with `uniform_count` vec4
+/// uniforms uploaded before every frame's draws, consumed by the
+/// fragment shader. The uploads are part of the timed region.
+fn test_uniforms(
+    bench: &Benchmark, mode: UniformUpdate, baseline: &BenchmarkResult,
+) -> Result<BenchmarkResult, String> {
+    let count = bench.config.uniform_count;
+    let num_draws = bench.config.overdraw;
+    let fs = compile_shader(&uniform_fs_src(count, mode), gl::FRAGMENT_SHADER)?;
+    let uniform_program = link_program(&bench.vs, &fs)?;
+    unsafe {
+        gl::UseProgram(uniform_program.as_raw());
+    }
+    set_overdraw(&uniform_program, num_draws);
+
+    let mut buffer = 0;
+    let frame = Cell::new(0u32);
+    // every frame uploads slightly different values, so that
+    // the driver can't skip any of the updates
+    let values = move || {
+        frame.set(frame.get() + 1);
+        let base = (frame.get() % 256) as f32 / 256.0 / count as f32;
+        vec![[base, base, base, 1.0 / count as f32]; count]
+    };
+    let mut test = Test::new(
+        &format!("{} uniforms by {}", count, mode),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        num_draws,
+        Flags::DRAW,
+    );
+    match mode {
+        UniformUpdate::Calls => {
+            let locations = (0 .. count)
+                .map(|i| uniform_location(&uniform_program, &format!("u_Values[{}]", i)))
+                .collect::<Vec<_>>();
+            test.work = Some(Box::new(move || unsafe {
+                for (&location, value) in locations.iter().zip(values()) {
+                    gl::Uniform4f(location, value[0], value[1], value[2], value[3]);
+                }
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, num_draws as _);
+            }));
+        }
+        UniformUpdate::Ubo => {
+            let size = (count * 16) as GLsizeiptr;
+            unsafe {
+                gl::GenBuffers(1, &mut buffer);
+                gl::BindBuffer(gl::UNIFORM_BUFFER, buffer);
+                gl::BufferData(gl::UNIFORM_BUFFER, size, std::ptr::null(), gl::DYNAMIC_DRAW);
+                let name = std::ffi::CString::new("Values").unwrap();
+                let block = gl::GetUniformBlockIndex(uniform_program.as_raw(), name.as_ptr());
+                gl::UniformBlockBinding(uniform_program.as_raw(), block, 0);
+                gl::BindBufferBase(gl::UNIFORM_BUFFER, 0, buffer);
+            }
+            test.work = Some(Box::new(move || unsafe {
+                let data = values();
+                gl::BufferSubData(gl::UNIFORM_BUFFER, 0, size, data.as_ptr() as *const _);
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, num_draws as _);
+            }));
+        }
+    }
+    check_gl("setting up the uniforms")?;
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let cost = result.fullscreen_ns as f64 - baseline.fullscreen_ns as f64;
+            println!("\tcost over '{}': {:+.3} ms", baseline.name, cost / 1.0e6);
+        }
+    }
+
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+        gl::BindBuffer(gl::UNIFORM_BUFFER, 0);
+        gl::DeleteBuffers(1, &buffer);
+    }
+    result
+}
+
+/// Measures uploading a `size` square RGBA8 texture with
+/// `glTexSubImage2D` once per frame. The timer queries only
+/// see the GPU side of the transfer, not the driver's copy.
+fn test_upload(bench: &Benchmark, size: u32) -> Result<BenchmarkResult, String> {
+    let texels = vec![0x80u8; size as usize * size as usize * 4];
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexStorage2D(gl::TEXTURE_2D, 1, gl::RGBA8, size as _, size as _);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+    }
+    check_gl("creating the upload texture")?;
+
+    let mut test = Test::new(&format!("upload {0}x{0}", size), 0, 1, Flags::DRAW);
+    test.work = Some(Box::new(move || unsafe {
+        gl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, size as _, size as _,
+            gl::RGBA, gl::UNSIGNED_BYTE, texels.as_ptr() as *const _);
+    }));
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let bytes = size as f64 * size as f64 * 4.0;
+            println!("\tupload rate: {:.0} MB/s", bytes / result.fullscreen_ns as f64 * 1.0e3);
+        }
+    }
+
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    result
+}
+
+/// Measures the color and depth test with every frame read back into
+/// one of `buffers` pixel buffers, then mapped. With a single buffer,
+/// mapping waits for the frame just drawn; with two, it maps the one
+/// of the previous frame, which the GPU should be done with. The time
+/// the CPU spends mapping and copying the pixels out is reported apart,
+/// as the timer queries only see the GPU side of the transfer.
+fn test_readback(
+    bench: &Benchmark, buffers: usize, baseline: &BenchmarkResult
+) -> Result<BenchmarkResult, String> {
+    let (width, height) = bench.size();
+    let size = width as usize * height as usize * 4;
+    let mut pbos = vec![0; buffers];
+    unsafe {
+        gl::GenBuffers(buffers as _, pbos.as_mut_ptr());
+        for &pbo in &pbos {
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, pbo);
+            gl::BufferData(gl::PIXEL_PACK_BUFFER, size as _, std::ptr::null(), gl::STREAM_READ);
+        }
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+    }
+    check_gl("creating the pixel buffers")?;
+    let num_draws = bench.config.overdraw;
+    set_overdraw(&bench.program, num_draws);
+
+    let map_time = Rc::new(Cell::new(Duration::new(0, 0)));
+    let frames = Rc::new(Cell::new(0usize));
+    let (work_map_time, work_frames, work_pbos) = (Rc::clone(&map_time), Rc::clone(&frames), pbos.clone());
+    let pixels = RefCell::new(vec![0u8; size]);
+    let mut test = Test::new(
+        &format!("readback through {} pixel buffer{}", buffers, if buffers > 1 { "s" } else { "" }),
+        gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+        num_draws,
+        Flags::DRAW,
+    );
+    test.work = Some(Box::new(move || unsafe {
+        let frame = work_frames.get();
+        work_frames.set(frame + 1);
+        gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, num_draws as _);
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, work_pbos[frame % buffers]);
+        gl::ReadPixels(0, 0, width as _, height as _, gl::RGBA, gl::UNSIGNED_BYTE, std::ptr::null_mut());
+        // the first frame has no previous one to map
+        if buffers == 1 || frame > 0 {
+            let start = Instant::now();
+            gl::BindBuffer(gl::PIXEL_PACK_BUFFER, work_pbos[(frame + 1) % buffers]);
+            let mapping = gl::MapBufferRange(gl::PIXEL_PACK_BUFFER, 0, size as _, gl::MAP_READ_BIT);
+            std::ptr::copy_nonoverlapping(mapping as *const u8, pixels.borrow_mut().as_mut_ptr(), size);
+            gl::UnmapBuffer(gl::PIXEL_PACK_BUFFER);
+            work_map_time.set(work_map_time.get() + start.elapsed());
+        }
+        gl::BindBuffer(gl::PIXEL_PACK_BUFFER, 0);
+    }));
+    let result = bench.run(&test);
+    if let Ok(ref result) = result {
+        if bench.config.prints_runs() {
+            let cost = result.fullscreen_ns as f64 * num_draws as f64
+                - baseline.fullscreen_ns as f64 * baseline.num_draws as f64;
+            let map_ns = map_time.get().as_nanos() as f64 / frames.get().max(1) as f64;
+            println!("\tcost over '{}': {:+.3} ms", baseline.name, cost / 1.0e6);
+            println!("\tdownload rate: {:.0} MB/s", size as f64 / cost.max(1.0) * 1.0e3);
+            println!("\tmapping and copying: {:.3} ms per frame", map_ns / 1.0e6);
+        }
+    }
+
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+        gl::DeleteBuffers(buffers as _, pbos.as_ptr());
+    }
+    result
+}
+
+/// Measures the color and depth test with a fence inserted after the
+/// draws of every frame, timing on the CPU how long `glClientWaitSync`
+/// blocks until the GPU signals it. The latencies are trimmed like the
+/// frame times, before reporting their distribution.
+fn test_fence_latency(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    let num_draws = config.overdraw;
+    set_overdraw(&bench.program, num_draws);
+
+    let latencies = Rc::new(RefCell::new(Vec::with_capacity(config.num_queries)));
+    let work_latencies = Rc::clone(&latencies);
+    let batch_latencies = Rc::clone(&latencies);
+    let mut test = Test::new(
+        "fence latency", gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT, num_draws, Flags::DRAW,
+    );
+    test.start_batch = Some(Box::new(move || batch_latencies.borrow_mut().clear()));
+    test.work = Some(Box::new(move || unsafe {
+        gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, num_draws as _);
+        let fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+        let start = Instant::now();
+        gl::ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, u64::MAX);
+        work_latencies.borrow_mut().push(start.elapsed().as_nanos() as u64);
+        gl::DeleteSync(fence);
+    }));
+    let result = bench.run(&test);
+    if result.is_ok() && config.prints_runs() {
+        let latencies = trimmed_samples(config, &latencies.borrow());
+        print_cpu_durations(config, "fence latency", &latencies);
+    }
+
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    result
+}
+
+/// Measures the color and depth test twice, once waiting for the GPU
+/// with `glFinish` after the draws of every frame, then with `glFlush`
+/// and `glClientWaitSync` on a fence, timing both waits on the CPU.
+fn test_finish_vs_flush(bench: &Benchmark) -> Result<Vec<BenchmarkResult>, String> {
+    let config = bench.config;
+    let num_draws = config.overdraw;
+    set_overdraw(&bench.program, num_draws);
+
+    let mut results = Vec::with_capacity(2);
+    let mut medians = Vec::with_capacity(2);
+    for &finish in &[true, false] {
+        let durations = Rc::new(RefCell::new(Vec::with_capacity(config.num_queries)));
+        let work_durations = Rc::clone(&durations);
+        let batch_durations = Rc::clone(&durations);
+        let (name, wait) = if finish {
+            ("synchronized by glFinish", "glFinish")
+        } else {
+            ("synchronized by glFlush and a fence", "glFlush and wait")
+        };
+        let clear_mask = gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT;
+        let mut test = Test::new(name, clear_mask, num_draws, Flags::DRAW);
+        test.start_batch = Some(Box::new(move || batch_durations.borrow_mut().clear()));
+        test.work = Some(Box::new(move || unsafe {
+            gl::DrawArraysInstanced(gl::TRIANGLES, 0, 3, num_draws as _);
+            let start = Instant::now();
+            if finish {
+                gl::Finish();
+            } else {
+                let fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+                gl::Flush();
+                gl::ClientWaitSync(fence, 0, u64::MAX);
+                gl::DeleteSync(fence);
+            }
+            work_durations.borrow_mut().push(start.elapsed().as_nanos() as u64);
+        }));
+        let result = bench.run(&test)?;
+        let durations = trimmed_samples(config, &durations.borrow());
+        if config.prints_runs() {
+            print_cpu_durations(config, wait, &durations);
+        }
+        medians.push(stats::median(&durations));
+        results.push(result);
+    }
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    if config.prints_runs() {
+        println!("glFinish over glFlush and glClientWaitSync: {:+.3} ms",
+            (medians[0] as f64 - medians[1] as f64) / 1.0e6);
+    }
+    Ok(results)
+}
+
+/// Measures `glGenerateMipmap` on a `size` square RGBA8 texture,
+/// regenerating its whole mip chain from the base level every frame.
+fn test_mipmap(bench: &Benchmark, size: u32) -> Result<BenchmarkResult, String> {
+    let levels = 32 - size.leading_zeros();
+    let texels = vec![0x80u8; size as usize * size as usize * 4];
+    let mut texture = 0;
+    unsafe {
+        gl::GenTextures(1, &mut texture);
+        gl::BindTexture(gl::TEXTURE_2D, texture);
+        gl::TexStorage2D(gl::TEXTURE_2D, levels as _, gl::RGBA8, size as _, size as _);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        gl::TexSubImage2D(gl::TEXTURE_2D, 0, 0, 0, size as _, size as _,
+            gl::RGBA, gl::UNSIGNED_BYTE, texels.as_ptr() as *const _);
+    }
+    check_gl("creating the mipmapped texture")?;
+
+    let mut test = Test::new(
+        &format!("mipmap generation {0}x{0}, {1} levels", size, levels), 0, 1, Flags::DRAW,
+    );
+    test.work = Some(Box::new(|| unsafe {
+        gl::GenerateMipmap(gl::TEXTURE_2D);
+    }));
+    let result = bench.run(&test);
+
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+        gl::DeleteTextures(1, &texture);
+    }
+    result
+}
+
+/// Measures the color and depth test once per instance count,
+/// every instance passing the depth test.
+fn test_instance_sweep(bench: &Benchmark, counts: &[usize]) -> Result<Vec<BenchmarkResult>, String> {
+    let mut results = Vec::with_capacity(counts.len());
+    for &count in counts {
+        set_overdraw(&bench.program, count);
+        let result = bench.run(&Test::new(
+            &format!("instance sweep {}", count),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            count,
+            Flags::DRAW,
+        ));
+        results.push(result?);
+    }
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+
+    if bench.config.prints_runs() {
+        println!("Instance sweep:");
+        println!("| instances | frame time | full-screen time |");
+        for result in &results {
+            println!("| {} | {:.2} ms | {:.2} ms |", result.num_draws,
+                (result.fullscreen_ns * result.num_draws as u64) as f32 / 1.0e6,
+                result.fullscreen_ns as f32 / 1.0e6);
+        }
+    }
+    Ok(results)
+}
+
+/// Measures the triangle rate drawing each number of triangles, an
+/// eighth of a pixel wide so that they barely produce any fragments,
+/// leaving the vertex processing and the primitive assembly as the
+/// bottleneck.
+fn test_vertex_sweep(bench: &Benchmark, counts: &[usize]) -> Result<Vec<BenchmarkResult>, String> {
+    let (width, height) = bench.size();
+    let vs = compile_shader(CORNERS_VS_SRC, gl::VERTEX_SHADER)?;
+    let tiny_program = link_program(&vs, &bench.fs)?;
+    unsafe {
+        gl::UseProgram(tiny_program.as_raw());
+        gl::Uniform2f(uniform_location(&tiny_program, "u_TriangleSize"),
+            0.25 / width as f32, 0.25 / height as f32);
+    }
+
+    let results = counts.iter().map(|&count| {
+        let columns = cells_columns(count);
+        let rows = count.div_ceil(columns);
+        unsafe {
+            gl::Uniform1i(uniform_location(&tiny_program, "u_Columns"), columns as _);
+            gl::Uniform2f(uniform_location(&tiny_program, "u_CellSize"),
+                2.0 / columns as f32, 2.0 / rows as f32);
+        }
+        let mut test = Test::new(
+            &format!("vertex sweep {}", count),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            1,
+            Flags::DRAW,
+        );
+        test.vertex_count = 3 * count;
+        bench.run(&test)
+    }).collect::<Result<Vec<_>, _>>();
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+    }
+    let results = results?;
+
+    if bench.config.prints_runs() {
+        println!("Vertex sweep:");
+        println!("| triangles | frame time | triangle rate |");
+        for (&count, result) in counts.iter().zip(&results) {
+            println!("| {} | {:.2} ms | {:.1} Mtri/s |", count,
+                result.fullscreen_ns as f32 / 1.0e6,
+                count as f64 / result.fullscreen_ns as f64 * 1.0e3);
+        }
+    }
+    Ok(results)
+}
+
+/// Pixels of a `width` by `height` cell covered by the right triangle
+/// in its corner, with legs of `size` pixels, by inclusion-exclusion
+/// of the parts sticking out of it.
+fn clipped_triangle_area(size: u32, width: u32, height: u32) -> f64 {
+    let outside = |overhang: i64| 0.5 * (overhang.max(0) as f64).powi(2);
+    let (size, width, height) = (size as i64, width as i64, height as i64);
+    outside(size) - outside(size - width) - outside(size - height) + outside(size - width - height)
+}
+
+/// Measures the screen tiled with triangles of each leg length in
+/// pixels, one per square cell of the same size, exposing the cost of
+/// the partially covered quads of small triangles.
+fn test_coverage_sweep(bench: &Benchmark, sizes: &[u32]) -> Result<Vec<BenchmarkResult>, String> {
+    let (width, height) = bench.size();
+    let vs = compile_shader(CORNERS_VS_SRC, gl::VERTEX_SHADER)?;
+    let coverage_program = link_program(&vs, &bench.fs)?;
+    unsafe {
+        gl::UseProgram(coverage_program.as_raw());
+    }
+
+    let mut covered = Vec::with_capacity(sizes.len());
+    let results = sizes.iter().map(|&size| {
+        let (columns, rows) = (width.div_ceil(size), height.div_ceil(size));
+        let (last_width, last_height) = (width - (columns - 1) * size, height - (rows - 1) * size);
+        covered.push(
+            clipped_triangle_area(size, size, size) * ((columns - 1) * (rows - 1)) as f64 +
+            clipped_triangle_area(size, last_width, size) * (rows - 1) as f64 +
+            clipped_triangle_area(size, size, last_height) * (columns - 1) as f64 +
+            clipped_triangle_area(size, last_width, last_height)
+        );
+        let cell_size = (2.0 * size as f32 / width as f32, 2.0 * size as f32 / height as f32);
+        unsafe {
+            gl::Uniform1i(uniform_location(&coverage_program, "u_Columns"), columns as _);
+            gl::Uniform2f(uniform_location(&coverage_program, "u_CellSize"), cell_size.0, cell_size.1);
+            gl::Uniform2f(uniform_location(&coverage_program, "u_TriangleSize"), cell_size.0, cell_size.1);
+        }
+        let mut test = Test::new(
+            &format!("coverage sweep {}px", size),
+            gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            1,
+            Flags::DRAW,
+        );
+        test.vertex_count = 3 * (columns * rows) as usize;
+        bench.run(&test)
+    }).collect::<Result<Vec<_>, _>>();
+    unsafe {
+        gl::UseProgram(bench.program.as_raw());
+    }
+    let results = results?;
+
+    if bench.config.prints_runs() {
+        let screen = width as f64 * height as f64;
+        println!("Coverage sweep:");
+        println!("| triangle size | screen coverage per triangle | frame time | fill rate |");
+        for ((&size, result), &pixels) in sizes.iter().zip(&results).zip(&covered) {
+            let coverage = clipped_triangle_area(size, width, height) / screen;
+            println!("| {} px | {:.4}% | {:.2} ms | {:.0} Mpix/s |", size, coverage * 100.0,
+                result.fullscreen_ns as f32 / 1.0e6, pixels / result.fullscreen_ns as f64 * 1.0e3);
+        }
+    }
+    Ok(results)
+}
+
+type MainTest = fn(&Benchmark) -> Result<BenchmarkResult, String>;
+
+/// Tests that `--tests` selects by name, in their default order.
+static MAIN_TESTS: &[(&str, MainTest)] = &[
+    ("color", test_color),
+    ("depth-reject", test_depth_reject),
+    ("clear", test_clear),
+];
+
+/// Names of the main tests, which `--tests` picks from.
+pub fn test_names() -> Vec<&'static str> {
+    MAIN_TESTS.iter().map(|&(name, _)| name).collect()
+}
+
+fn main_test(name: &str) -> Result<MainTest, String> {
+    MAIN_TESTS
+        .iter()
+        .find(|&&(test, _)| test == name)
+        .map(|&(_, run)| run)
+        .ok_or_else(|| format!("unknown test '{}', the tests are: {}", name, test_names().join(", ")))
+}
+
+/// Measures `overdraw` full-screen instances, all passing the depth test.
+fn test_color(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    unsafe {
+        let [r, g, b, a] = config.clear_color;
+        gl::ClearColor(r, g, b, a);
+    }
+    set_overdraw(&bench.program, config.overdraw);
+    let result = bench.run(&Test::new(
+        &bench.color_name, gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT, config.overdraw, Flags::DRAW,
+    ));
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    result
+}
+
+/// Measures `num_rejects` full-screen instances, all but the first
+/// failing the depth test.
+fn test_depth_reject(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    // the first instance of the rejection tests is the nearest, with
+    // every following one farther away and failing the depth test
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"),
+            -1.0 / config.num_rejects as f32);
+    }
+
+    unsafe {
+        gl::Flush();
+        gl::ClearColor(1.0, 0.3, 0.3, 1.0);
+    }
+
+    let clear_mask = gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT;
+    let mut test = Test::new("depth rejected", clear_mask, config.num_rejects, Flags::DRAW);
+    test.count_samples = true;
+    let result = bench.run(&test)?;
+    if let Some(samples) = result.samples_passed {
+        let (width, height) = bench.size();
+        // only the first instance is expected to pass
+        if samples > width as u64 * height as u64 * config.msaa.max(1) as u64 * 3 / 2 {
+            eprintln!("warning: {} samples passed the depth test, \
+                the instances behind the first one aren't being rejected", samples);
+        }
+    }
+    check_depth_rejection(bench)?;
+    unsafe {
+        gl::Uniform1f(uniform_location(&bench.program, "u_DepthStep"), 0.0);
+    }
+    Ok(result)
+}
+
+/// Measures `num_rejects` clears of the whole render target.
+fn test_clear(bench: &Benchmark) -> Result<BenchmarkResult, String> {
+    let config = bench.config;
+    unsafe {
+        gl::ClearColor(1.0, 0.3, 0.3, 1.0);
+    }
+    bench.run(&Test::new("color clear", gl::COLOR_BUFFER_BIT, config.num_rejects, Flags::CLEAR))
+}
+
+/// Runs every enabled test once, starting with the main tests, by
+/// default the color and depth test that the Markdown table relies on.
+fn run_suite(bench: &Benchmark) -> Result<Vec<BenchmarkResult>, String> {
+    let config = bench.config;
+    let mut tests = Vec::new();
+    let names = if config.tests.is_empty() {
+        test_names()
+    } else {
+        config.tests.iter().map(String::as_str).collect()
+    };
+    for name in &names {
+        tests.push(main_test(name)?(bench)?);
+    }
+    let color = names.iter().position(|&name| name == "color");
+    // the following tests start from the state the main tests leave,
+    // whichever of them ran
+    unsafe {
+        gl::ClearColor(1.0, 0.3, 0.3, 1.0);
+    }
+    set_overdraw(&bench.program, config.overdraw);
+    let needs_color = config.depth_only || config.hdr || config.srgb || config.uniforms.is_some()
+        || config.indirect || config.image_store || config.readback.is_some();
+    let color = match color {
+        Some(color) => color,
+        None if needs_color => {
+            eprintln!("warning: also measuring the color test, which other tests compare with");
+            tests.push(test_color(bench)?);
+            unsafe {
+                gl::ClearColor(1.0, 0.3, 0.3, 1.0);
+            }
+            tests.len() - 1
+        }
+        // not compared with by any of the enabled tests
+        None => 0,
+    };
+
+    match config.primitive {
+        Primitive::Triangles => {}
+        Primitive::Points => tests.push(test_points(bench)?),
+        Primitive::Lines => tests.push(test_lines(bench)?),
+    }
+    // the attribute fetch test includes the plain triangle grid
+    if config.attribs {
+        tests.extend(test_attribs(bench)?);
+    } else if let Some(tris_per_draw) = config.tris_per_draw {
+        tests.push(test_triangle_grid(bench, tris_per_draw)?);
+    }
+    let buffer_storage = !is_gles() && (supports_gl(4, 4) || has_extension("GL_ARB_buffer_storage"));
+    match config.stream {
+        Some(StreamMode::Persistent) if !buffer_storage => {
+            eprintln!("warning: skipping the persistent streaming test, it requires GL 4.4 or ARB_buffer_storage");
+        }
+        Some(StreamMode::Persistent) => {
+            let orphaned = test_stream(bench, StreamMode::Orphan, None)?;
+            let persistent = test_stream(bench, StreamMode::Persistent, Some(&orphaned))?;
+            tests.push(orphaned);
+            tests.push(persistent);
+        }
+        Some(mode) => tests.push(test_stream(bench, mode, None)?),
+        None => {}
+    }
+    if config.blend {
+        tests.push(test_blend(bench)?);
+    }
+    let texture_test = tests.len();
+    if config.texture {
+        tests.push(test_texture(bench)?);
+    }
+    if !config.aniso.is_empty() {
+        tests.extend(test_aniso(bench, &config.aniso)?);
+    }
+    if let Some(size) = config.cubemap {
+        tests.push(test_cubemap(bench, size)?);
+    }
+    if let Some(size) = config.texture_3d {
+        tests.push(test_texture_3d(bench, size)?);
+    }
+    if let Some(layers) = config.texture_array {
+        let plain = if config.texture { Some(&tests[texture_test]) } else { None };
+        let result = test_texture_array(bench, layers, plain)?;
+        tests.push(result);
+    }
+    if config.render_to_texture {
+        tests.push(test_render_to_texture(bench)?);
+    }
+    if config.mrt > 0 {
+        tests.push(test_mrt(bench)?);
+    }
+    if config.stencil {
+        tests.push(test_stencil(bench)?);
+    }
+    if let Some(fraction) = config.discard {
+        tests.push(test_discard(bench, fraction)?);
+    }
+    for &ops in &config.alu_ops {
+        tests.push(test_alu(bench, ops)?);
+    }
+    if !config.sweep_instances.is_empty() {
+        tests.extend(test_instance_sweep(bench, &config.sweep_instances)?);
+    }
+    if !config.sweep_resolution.is_empty() {
+        tests.extend(test_resolution_sweep(bench, &config.sweep_resolution)?);
+    }
+    if !config.sweep_vertices.is_empty() {
+        tests.extend(test_vertex_sweep(bench, &config.sweep_vertices)?);
+    }
+    if !config.sweep_coverage.is_empty() {
+        tests.extend(test_coverage_sweep(bench, &config.sweep_coverage)?);
+    }
+    if let Some(size) = config.upload {
+        if supports_tex_storage() {
+            tests.push(test_upload(bench, size)?);
+        } else {
+            eprintln!("warning: skipping the upload test, it requires GL 4.2 or GLES 3.0");
+        }
+    }
+    if let Some(size) = config.mipmap {
+        if supports_tex_storage() {
+            tests.push(test_mipmap(bench, size)?);
+        } else {
+            eprintln!("warning: skipping the mipmap test, it requires GL 4.2 or GLES 3.0");
+        }
+    }
+    if config.wireframe {
+        if is_gles() {
+            eprintln!("warning: skipping the wireframe test, GLES has no polygon modes");
+        } else {
+            tests.push(test_wireframe(bench)?);
+        }
+    }
+    if config.cull {
+        tests.extend(test_cull(bench)?);
+    }
+    if config.depth_only {
+        let result = test_depth_only(bench, &tests[color])?;
+        tests.push(result);
+    }
+    if config.hdr {
+        let result = test_hdr(bench, &tests[color])?;
+        tests.push(result);
+    }
+    if config.srgb {
+        let result = test_srgb(bench, &tests[color])?;
+        tests.push(result);
+    }
+    if let Some(mode) = config.uniforms {
+        let result = test_uniforms(bench, mode, &tests[color])?;
+        tests.push(result);
+    }
+    if config.compute {
+        if supports_compute() {
+            tests.push(test_compute(bench)?);
+        } else {
+            eprintln!("warning: skipping the compute test, it requires GL 4.3 or GLES 3.1");
+        }
+    }
+    if config.indirect {
+        let supported = if is_gles() { supports_gl(3, 1) } else { supports_gl(4, 0) };
+        if !supported {
+            eprintln!("warning: skipping the indirect draw test, it requires GL 4.0 or GLES 3.1");
+        } else {
+            let result = test_indirect(bench, &tests[color])?;
+            tests.push(result);
+        }
+    }
+    if let Some(count) = config.multi_draw {
+        if is_gles() || !supports_gl(4, 3) {
+            eprintln!("warning: skipping the multi-draw test, it requires desktop GL 4.3");
+        } else {
+            tests.extend(test_multi_draw(bench, count)?);
+        }
+    }
+    if config.image_store {
+        let mut fragment_images = 0;
+        if supports_compute() {
+            unsafe { gl::GetIntegerv(gl::MAX_FRAGMENT_IMAGE_UNIFORMS, &mut fragment_images) };
+        }
+        if fragment_images == 0 {
+            eprintln!("warning: skipping the image store test, it requires GL 4.3 \
+                or GLES 3.1 with fragment shader images");
+        } else if is_gles() && config.color_format == ColorFormat::R11fG11fB10f {
+            eprintln!("warning: skipping the image store test, GLES has no r11f_g11f_b10f images");
+        } else {
+            let result = test_image_store(bench, &tests[color])?;
+            tests.push(result);
+        }
+    }
+    if let Some(access) = config.ssbo {
+        if supports_compute() {
+            tests.push(test_ssbo(bench, access)?);
+        } else {
+            eprintln!("warning: skipping the storage buffer test, it requires GL 4.3 or GLES 3.1");
+        }
+    }
+    if let Some(count) = config.transform_feedback {
+        if supports_gl(3, 0) {
+            tests.push(test_transform_feedback(bench, count)?);
+        } else {
+            eprintln!("warning: skipping the transform feedback test, it requires GL 3.0 or GLES 3.0");
+        }
+    }
+    if let Some(buffers) = config.readback {
+        let result = test_readback(bench, buffers, &tests[color])?;
+        tests.push(result);
+    }
+    if config.fence_latency {
+        if is_gles() || supports_gl(3, 2) {
+            tests.push(test_fence_latency(bench)?);
+        } else {
+            eprintln!("warning: skipping the fence latency test, it requires GL 3.2 or GLES 3.0");
+        }
+    }
+    if config.finish_vs_flush {
+        if is_gles() || supports_gl(3, 2) {
+            tests.extend(test_finish_vs_flush(bench)?);
+        } else {
+            eprintln!("warning: skipping the glFinish test, it requires GL 3.2 or GLES 3.0");
+        }
+    }
+    Ok(tests)
+}
+
+
+impl<'a> Benchmark<'a> {
+    /// Sets up the shared GL objects in the current context, which
+    /// presents the frames to `context`. The GL function pointers
+    /// must already be loaded.
+    pub fn new(config: &'a Config, context: &'a dyn Surface) -> Result<Self, String> {
+        config.validate()?;
+        for name in &config.tests {
+            main_test(name)?;
+        }
+        let khr_debug = supports_gl(4, 3) || has_extension("GL_KHR_debug");
+        if config.debug_gl {
+            if khr_debug {
+                debug::install_callback();
+            } else {
+                eprintln!("warning: debug output is not supported, ignoring '--debug-gl'");
+            }
+        }
+
+        // Create GLSL shaders
+        let vs = load_shader(config.vs.as_deref(), VS_SRC, gl::VERTEX_SHADER)?;
+        let fs = load_shader(config.fs.as_deref(), FS_SRC, gl::FRAGMENT_SHADER)?;
+        let program = link_program(&vs, &fs)?;
+        let gl_string = |name| unsafe {
+            CStr::from_ptr(gl::GetString(name) as _).to_string_lossy().into_owned()
+        };
+        let mut bench = Benchmark {
+            config,
+            context,
+            target_fbo: Cell::new(0),
+            target_size: Cell::new(None),
+            queries: QueryPool::new(timer::QUERY_RING_FRAMES * timer::queries_per_frame(config.timing)),
+            program,
+            vs,
+            fs,
+            cpu_timing: Cell::new(false),
+            disjoint_timer: false,
+            khr_debug: false,
+            closed: Cell::new(false),
+            last_frame: RefCell::new(None),
+            stats_queries: None,
+            samples_query: None,
+            vao: Vao::new(),
+            target: None,
+            color_name: String::new(),
+            renderer: gl_string(gl::RENDERER),
+            version: gl_string(gl::VERSION),
+            environment: query_environment(),
+            on_sample: RefCell::new(None),
+            frame_log: RefCell::new(None),
+        };
+        if let Some(ref path) = config.frames_out {
+            *bench.frame_log.get_mut() = Some(FrameLog::create(path)?);
+        }
+
+        unsafe {
+            gl::BindVertexArray(bench.vao.as_raw());
+            gl::UseProgram(bench.program.as_raw());
+        }
+        if khr_debug {
+            debug::label(gl::PROGRAM, bench.program.as_raw(), "full-screen triangle");
+            debug::label(gl::VERTEX_ARRAY, bench.vao.as_raw(), "attributeless vertex array");
+        }
+        bench.khr_debug = khr_debug;
+        check_gl("setting up the pipeline")?;
+        // timer queries are core since GL 3.3, and an extension on GLES, whose
+        // entry points the bindings load in place of the desktop ones
+        let gles = is_gles();
+        let timer_queries = if gles {
+            has_extension("GL_EXT_disjoint_timer_query")
+        } else {
+            supports_gl(3, 3)
+        };
+        if !timer_queries {
+            eprintln!("warning: timer queries are not supported, falling back to CPU timing");
+            bench.cpu_timing.set(true);
+        }
+        bench.disjoint_timer = has_extension("GL_EXT_disjoint_timer_query");
+        if config.pipeline_stats {
+            if supports_gl(4, 6) || has_extension("GL_ARB_pipeline_statistics_query") {
+                bench.stats_queries = Some(QueryPool::new(2));
+            } else {
+                eprintln!("warning: pipeline statistics are not supported, only timing the draws");
+            }
+        }
+        if config.samples_passed {
+            if gles {
+                eprintln!("warning: GLES can't count the samples passed, ignoring '--samples-passed'");
+            } else {
+                bench.samples_query = Some(QueryPool::new(1));
+            }
+        }
+
+        unsafe {
+            if gles {
+                gl::ClearDepthf(config.clear_depth);
+            } else {
+                gl::ClearDepth(config.clear_depth as f64);
+            }
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(depth_func(config.depth_func));
+            gl::DepthMask(gl::TRUE);
+            // the tests move the nearer instances towards -1, which
+            // the reversed range maps to the greater depths
+            if config.depth_func.is_reversed() {
+                if gles {
+                    gl::DepthRangef(1.0, 0.0);
+                } else {
+                    gl::DepthRange(1.0, 0.0);
+                }
+            }
+        }
+
+        // offscreen, or at a resolution other than the window's, the tests
+        // render to a framebuffer object instead
+        let target_size = match config.fbo_resolution {
+            Some(size) => Some((size.width, size.height)),
+            None if context.is_headless() => Some(context.size()),
+            None => None,
+        };
+        if let Some((width, height)) = target_size {
+            let depth_format = if config.stencil { gl::DEPTH24_STENCIL8 } else { gl::DEPTH_COMPONENT24 };
+            let color_format = if config.srgb {
+                gl::SRGB8_ALPHA8
+            } else {
+                internal_format(config.color_format)
+            };
+            let framebuffer = Framebuffer::with_formats(width, height, 1, color_format, depth_format)?;
+            bench.set_target(framebuffer.fbo, target_size);
+            bench.target = Some(framebuffer);
+        }
+        if target_size.is_none() {
+            // GL initially sizes the viewport to the default framebuffer,
+            // which a size in logical pixels would fall short of
+            let mut viewport = [0; 4];
+            unsafe { gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr()) };
+            debug_assert_eq!((viewport[2] as u32, viewport[3] as u32), context.size(),
+                "the window size is not in physical pixels");
+        }
+        bench.bind_target();
+        let (width, height) = bench.size();
+        let profile = context_profile();
+        let requested = config.profile.map(|profile| match profile {
+            Profile::Core => "core",
+            Profile::Compat => "compat",
+        });
+        if let Some(requested) = requested.filter(|&requested| requested != profile) {
+            eprintln!("warning: requested a {} profile context, but got a {} one",
+                requested, profile);
+        }
+        if config.format == Format::Text {
+            println!("Renderer: {:?}", bench.renderer);
+            println!("Version: {:?}", bench.version);
+            println!("Profile: {}", profile);
+            println!("Screen: {}x{} resolution with {} hiDPI factor",
+                width, height, bench.hidpi_factor());
+            println!("Depth: {} test, cleared to {}", config.depth_func, config.clear_depth);
+            if config.caps {
+                bench.environment.print();
+            }
+            if config.list_extensions {
+                println!("Extensions:");
+                for extension in extensions() {
+                    println!("\t{}", extension.to_string_lossy());
+                }
+            }
+        }
+
+        let mut samples = 0;
+        if config.msaa > 1 {
+            unsafe {
+                // multisampling can't be turned off on GLES
+                if !gles {
+                    gl::Enable(gl::MULTISAMPLE);
+                }
+                gl::GetIntegerv(gl::SAMPLES, &mut samples);
+            }
+            if samples != config.msaa as GLint {
+                eprintln!("warning: requested {} samples, but the framebuffer has {}",
+                    config.msaa, samples);
+            }
+        }
+        bench.color_name = match samples {
+            0 | 1 => "color and depth".to_string(),
+            n => format!("color and depth {}x MSAA", n),
+        };
+        Ok(bench)
+    }
+
+    /// Runs the suite `repeat` times, returning the results of every
+    /// test, or their medians over the repeats.
+    pub fn measure(&self) -> Result<Vec<BenchmarkResult>, String> {
+        let config = self.config;
+        let mut repeats = Vec::with_capacity(config.repeat);
+        for i in 0 .. config.repeat {
+            if config.prints_runs() && config.repeat > 1 {
+                println!("Repeat {} of {}", i + 1, config.repeat);
+            }
+            repeats.push(run_suite(self)?);
+        }
+        if config.repeat == 1 {
+            return Ok(repeats.pop().unwrap());
+        }
+        Ok((0 .. repeats[0].len())
+            .map(|i| {
+                let runs = repeats.iter().map(|tests| &tests[i]).collect::<Vec<_>>();
+                BenchmarkResult::aggregate(&runs)
+            })
+            .collect())
+    }
+
+    /// Calls `callback` with the index and the time in nanoseconds of
+    /// every frame of every test, as soon as the test collected them.
+    pub fn on_sample<F: FnMut(usize, u64) + 'a>(&mut self, callback: F) {
+        *self.on_sample.get_mut() = Some(Box::new(callback));
+    }
+
+    /// Whether `measure` was interrupted by the window being closed.
+    pub fn is_closed(&self) -> bool {
+        self.closed.get()
+    }
+
+    /// Draws one more frame like the color and depth test, and checks
+    /// whether the drawn color made it to the render target.
+    pub fn verify(&self) -> Result<bool, String> {
+        verify_output(self)
+    }
+
+    /// Saves the last frame captured by the tests as a PNG image.
+    pub fn save_screenshot(&self, path: &Path) -> Result<(), String> {
+        match *self.last_frame.borrow() {
+            Some(ref frame) => frame.save_png(path),
+            None => {
+                eprintln!("warning: no test ran all its frames, there is no screenshot to save");
+                Ok(())
+            }
+        }
+    }
+
+    /// Describes the context and the render target along with the results.
+    pub fn report(&self, tests: Vec<BenchmarkResult>) -> Report {
+        let (width, height) = self.size();
+        Report {
+            os: std::env::consts::OS,
+            renderer: self.renderer.clone(),
+            version: self.version.clone(),
+            width,
+            height,
+            hidpi: self.hidpi_factor(),
+            depth_func: self.config.depth_func.to_string(),
+            clear_depth: self.config.clear_depth,
+            environment: self.environment.clone(),
+            default_tests: self.config.tests.is_empty() || self.config.tests == test_names(),
+            tests,
+        }
+    }
+}
+
+/// Measures the fill rate in the context current on the calling thread,
+/// rendering offscreen at `config.resolution`. The GL function pointers
+/// must already be loaded, with `gl_bench::gl::load_with`.
+pub fn run(config: &Config) -> Result<Vec<BenchmarkResult>, String> {
+    let context = ExistingContext {
+        width: config.resolution.width,
+        height: config.resolution.height,
+    };
+    let bench = Benchmark::new(config, &context)?;
+    bench.measure()
+}
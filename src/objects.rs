@@ -0,0 +1,111 @@
+//! GL objects owned by the benchmark, deleted when dropped
+
+use std::ops::Deref;
+
+use gl;
+use gl::types::*;
+
+/// A compiled shader.
+pub struct Shader(GLuint);
+
+impl Shader {
+    /// Takes ownership of the given shader object.
+    pub fn from_raw(shader: GLuint) -> Self {
+        Shader(shader)
+    }
+
+    pub fn as_raw(&self) -> GLuint {
+        self.0
+    }
+}
+
+impl Drop for Shader {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteShader(self.0) };
+    }
+}
+
+/// A linked program. Deleting a program in use only takes
+/// effect once another one gets bound.
+pub struct Program(GLuint);
+
+impl Program {
+    /// Takes ownership of the given program object.
+    pub fn from_raw(program: GLuint) -> Self {
+        Program(program)
+    }
+
+    pub fn as_raw(&self) -> GLuint {
+        self.0
+    }
+}
+
+impl Drop for Program {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteProgram(self.0) };
+    }
+}
+
+pub struct Vao(GLuint);
+
+impl Vao {
+    pub fn new() -> Self {
+        let mut vao = 0;
+        unsafe { gl::GenVertexArrays(1, &mut vao) };
+        Vao(vao)
+    }
+
+    pub fn as_raw(&self) -> GLuint {
+        self.0
+    }
+}
+
+impl Drop for Vao {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteVertexArrays(1, &self.0) };
+    }
+}
+
+pub struct Buffer(GLuint);
+
+impl Buffer {
+    pub fn new() -> Self {
+        let mut buffer = 0;
+        unsafe { gl::GenBuffers(1, &mut buffer) };
+        Buffer(buffer)
+    }
+
+    pub fn as_raw(&self) -> GLuint {
+        self.0
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteBuffers(1, &self.0) };
+    }
+}
+
+/// Query objects, dereferencing to their names.
+pub struct QueryPool(Vec<GLuint>);
+
+impl QueryPool {
+    pub fn new(count: usize) -> Self {
+        let mut queries = vec![0; count];
+        unsafe { gl::GenQueries(count as _, queries.as_mut_ptr()) };
+        QueryPool(queries)
+    }
+}
+
+impl Deref for QueryPool {
+    type Target = [GLuint];
+    fn deref(&self) -> &[GLuint] {
+        &self.0
+    }
+}
+
+impl Drop for QueryPool {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteQueries(self.0.len() as _, self.0.as_ptr()) };
+    }
+}
@@ -0,0 +1,339 @@
+//! Benchmark results and the formats they are reported in
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use serde_json;
+
+use stats;
+
+/// How the results are printed to stdout.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Human-readable progress and a Markdown table entry.
+    Text,
+    /// A single JSON object, with all the timings in nanoseconds.
+    Json,
+    /// A header row followed by one row per test.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format '{}'", s)),
+        }
+    }
+}
+
+/// Full-screen time below which a given percentage of the draws fall.
+#[derive(Serialize)]
+pub struct Percentile {
+    pub percent: f64,
+    pub ns: u64,
+}
+
+/// Timings of a single test.
+#[derive(Serialize)]
+pub struct BenchmarkResult {
+    pub name: String,
+    /// Frames the timings are computed from, after dropping the outliers.
+    pub samples: usize,
+    /// Instances drawn per frame.
+    pub num_draws: usize,
+    /// Average time of a single full-screen draw.
+    pub fullscreen_ns: u64,
+    /// Average time of a single draw, normalized to a million pixels.
+    pub megapixel_ns: u64,
+    /// Median time of a single full-screen draw.
+    pub median_ns: u64,
+    /// Sample standard deviation of the full-screen draw time.
+    pub std_dev_ns: u64,
+    /// Shortest and longest measured frames, with all their instances.
+    pub min_frame_ns: u64,
+    pub max_frame_ns: u64,
+    pub percentiles: Vec<Percentile>,
+    /// Number of frames excluded from the measurements.
+    pub outliers: usize,
+    /// Whether the frames were timed on the CPU, which is much coarser
+    /// than the timer queries.
+    pub cpu_timed: bool,
+    /// Average time of the clear and of the draw calls in a frame,
+    /// when timed with GPU timestamps.
+    pub clear_ns: Option<u64>,
+    pub draw_ns: Option<u64>,
+    /// Sample standard deviation of the full-screen time between the
+    /// repeats of the suite, when it is repeated.
+    pub repeat_std_dev_ns: Option<u64>,
+    /// Time spent waiting for the timer query results to be available.
+    pub query_wait_ns: u64,
+    /// Primitives generated and fragment shader invocations of the draws
+    /// of the last frame, with pipeline statistics.
+    pub primitives: Option<u64>,
+    pub fragment_invocations: Option<u64>,
+    /// Samples of the draws of the last frame passing the depth test,
+    /// for the tests counting them.
+    pub samples_passed: Option<u64>,
+}
+
+fn median_of<T, F: Fn(&T) -> u64>(items: &[T], field: F) -> u64 {
+    stats::median(&items.iter().map(field).collect::<Vec<_>>())
+}
+
+impl BenchmarkResult {
+    /// Combines the results of the same test over several repeats,
+    /// taking the median of every timing.
+    pub fn aggregate(runs: &[&BenchmarkResult]) -> BenchmarkResult {
+        let first = &runs[0];
+        let fullscreen = runs.iter().map(|run| run.fullscreen_ns).collect::<Vec<_>>();
+        let percentiles = first.percentiles
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Percentile {
+                percent: p.percent,
+                ns: median_of(runs, |run| run.percentiles[i].ns),
+            })
+            .collect();
+        BenchmarkResult {
+            name: first.name.clone(),
+            samples: median_of(runs, |run| run.samples as u64) as usize,
+            num_draws: first.num_draws,
+            fullscreen_ns: median_of(runs, |run| run.fullscreen_ns),
+            megapixel_ns: median_of(runs, |run| run.megapixel_ns),
+            median_ns: median_of(runs, |run| run.median_ns),
+            std_dev_ns: median_of(runs, |run| run.std_dev_ns),
+            min_frame_ns: median_of(runs, |run| run.min_frame_ns),
+            max_frame_ns: median_of(runs, |run| run.max_frame_ns),
+            percentiles,
+            outliers: median_of(runs, |run| run.outliers as u64) as usize,
+            cpu_timed: runs.iter().any(|run| run.cpu_timed),
+            clear_ns: first.clear_ns.map(|_| median_of(runs, |run| run.clear_ns.unwrap_or(0))),
+            draw_ns: first.draw_ns.map(|_| median_of(runs, |run| run.draw_ns.unwrap_or(0))),
+            repeat_std_dev_ns: Some(stats::std_dev(&fullscreen) as u64),
+            query_wait_ns: median_of(runs, |run| run.query_wait_ns),
+            primitives: first.primitives.map(|_| median_of(runs, |run| run.primitives.unwrap_or(0))),
+            fragment_invocations: first.fragment_invocations
+                .map(|_| median_of(runs, |run| run.fragment_invocations.unwrap_or(0))),
+            samples_passed: first.samples_passed
+                .map(|_| median_of(runs, |run| run.samples_passed.unwrap_or(0))),
+        }
+    }
+}
+
+/// Limits of the GL implementation the tests ran on.
+#[derive(Clone, Serialize)]
+pub struct Environment {
+    pub max_texture_size: i32,
+    pub max_samples: i32,
+    pub max_color_attachments: i32,
+    pub max_vertex_attribs: i32,
+    /// Video memory in MiB, as far as a vendor extension tells.
+    pub vram_total_mb: Option<u64>,
+    pub vram_available_mb: Option<u64>,
+}
+
+impl Environment {
+    pub fn print(&self) {
+        println!("Limits:");
+        println!("\tmax texture size: {}", self.max_texture_size);
+        println!("\tmax samples: {}", self.max_samples);
+        println!("\tmax color attachments: {}", self.max_color_attachments);
+        println!("\tmax vertex attributes: {}", self.max_vertex_attribs);
+        let mib = |size: Option<u64>| size.map_or("unknown".to_string(), |size| format!("{} MiB", size));
+        println!("\tvideo memory: {} total, {} available",
+            mib(self.vram_total_mb), mib(self.vram_available_mb));
+    }
+}
+
+/// Everything a run produces, in the shape of the JSON output.
+#[derive(Serialize)]
+pub struct Report {
+    pub os: &'static str,
+    pub renderer: String,
+    pub version: String,
+    pub width: u32,
+    pub height: u32,
+    pub hidpi: f32,
+    pub depth_func: String,
+    pub clear_depth: f32,
+    pub environment: Environment,
+    /// Whether the suite started with the color, depth reject and
+    /// clear tests, in this order, which the Markdown table needs.
+    #[serde(skip)]
+    pub default_tests: bool,
+    pub tests: Vec<BenchmarkResult>,
+}
+
+/// Timings of a previous run, read back from its saved report.
+#[derive(Deserialize)]
+pub struct Baseline {
+    tests: Vec<BaselineTest>,
+}
+
+#[derive(Deserialize)]
+struct BaselineTest {
+    name: String,
+    fullscreen_ns: u64,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read '{}': {}", path.display(), e))?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("failed to parse '{}': {}", path.display(), e))
+    }
+
+    /// Full-screen time of the `index`th test of the report in the
+    /// baseline. Tests are matched by name, in order, as several of
+    /// them may share a name.
+    fn fullscreen_ns(&self, tests: &[BenchmarkResult], index: usize) -> Option<u64> {
+        let name = &tests[index].name;
+        let occurrence = tests[.. index].iter().filter(|test| test.name == *name).count();
+        self.tests
+            .iter()
+            .filter(|test| test.name == *name)
+            .nth(occurrence)
+            .map(|test| test.fullscreen_ns)
+    }
+}
+
+fn csv_quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+/// CSV file receiving the time of every frame of every test, warmup
+/// included, as the tests complete.
+pub struct FrameLog {
+    file: BufWriter<File>,
+    path: String,
+}
+
+impl FrameLog {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let path = path.display().to_string();
+        let file = File::create(&path).map_err(|e| format!("failed to create '{}': {}", path, e))?;
+        let mut log = FrameLog { file: BufWriter::new(file), path };
+        log.write_line(format_args!("test,frame,ns,warmup"))?;
+        Ok(log)
+    }
+
+    fn write_line(&mut self, line: fmt::Arguments) -> Result<(), String> {
+        writeln!(self.file, "{}", line).map_err(|e| format!("failed to write '{}': {}", self.path, e))
+    }
+
+    /// Appends the frames of a test, the first `warmup` of which are
+    /// marked as such.
+    pub fn write(&mut self, test: &str, frames: &[u64], warmup: usize) -> Result<(), String> {
+        let name = csv_quote(test);
+        for (i, ns) in frames.iter().enumerate() {
+            self.write_line(format_args!("{},{},{},{}", name, i, ns, i < warmup))?;
+        }
+        self.file.flush().map_err(|e| format!("failed to write '{}': {}", self.path, e))
+    }
+}
+
+static TABLE_HEADER: &str = "\
+| OS | Version | Renderer | Resolution | hiDPI | Full-screen | Clear MP | Color MP | Depth reject MP |
+|----|---------|----------|------------|-------|-------------|----------|----------|-----------------|
+";
+
+impl Report {
+    /// Formats a row of the Markdown results table, out of the tests
+    /// in the default order: color, depth reject, clear.
+    fn table_row(&self) -> String {
+        debug_assert!(self.default_tests);
+        let (color, depth_reject, color_clear) = (&self.tests[0], &self.tests[1], &self.tests[2]);
+        format!("| {} | {:?} | {:?} | {}x{} | {} | {:.2} ms | {} mcs | {} mcs | {} mcs |",
+            self.os, self.version, self.renderer,
+            self.width, self.height, self.hidpi,
+            color.fullscreen_ns as f32 * 1.0e-6,
+            color_clear.megapixel_ns / 1000,
+            color.megapixel_ns / 1000,
+            depth_reject.megapixel_ns / 1000
+        )
+    }
+
+    /// Prints the median and run-to-run deviation of every test.
+    pub fn print_repeat_summary(&self, repeat: usize) {
+        println!("Medians over {} repeats:", repeat);
+        for test in &self.tests {
+            println!("\t{}: {:.2} ms ± {:.2} ms full-screen, {} mcs mega-pixel",
+                test.name,
+                test.fullscreen_ns as f32 / 1.0e6,
+                test.repeat_std_dev_ns.unwrap_or(0) as f32 / 1.0e6,
+                test.megapixel_ns / 1000);
+        }
+    }
+
+    pub fn print_table_entry(&self) {
+        if !self.default_tests {
+            return;
+        }
+        println!("Table entry:");
+        println!("{}", self.table_row());
+    }
+
+    /// Appends the table row to a Markdown file, starting
+    /// it with the table header if it doesn't exist yet.
+    pub fn append_markdown(&self, path: &Path) -> io::Result<()> {
+        if !self.default_tests {
+            eprintln!("warning: not appending to '{}', the table needs all the main tests \
+                in the default order", path.display());
+            return Ok(());
+        }
+        let is_new = !path.exists();
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if is_new {
+            file.write_all(TABLE_HEADER.as_bytes())?;
+        }
+        writeln!(file, "{}", self.table_row())?;
+        file.sync_all()
+    }
+
+    /// Writes the JSON report to `path`, to be loaded as a `Baseline`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self).unwrap())
+    }
+
+    /// Returns the change of the full-screen time of every test found
+    /// in the baseline, in percent, along with the test index. Tests
+    /// with no time in the baseline are skipped, as nothing compares to it.
+    pub fn compare(&self, baseline: &Baseline) -> Vec<(usize, f64)> {
+        (0 .. self.tests.len())
+            .filter_map(|i| {
+                let base = baseline.fullscreen_ns(&self.tests, i)?;
+                if base == 0 {
+                    eprintln!("warning: not comparing '{}', its baseline time is 0",
+                        self.tests[i].name);
+                    return None;
+                }
+                let delta = (self.tests[i].fullscreen_ns as f64 / base as f64 - 1.0) * 100.0;
+                Some((i, delta))
+            })
+            .collect()
+    }
+
+    pub fn print_json(&self) {
+        println!("{}", serde_json::to_string_pretty(self).unwrap());
+    }
+
+    pub fn print_csv(&self) {
+        println!("os,version,renderer,width,height,hidpi,test,fullscreen_ns,megapixel_ns,num_draws");
+        for test in &self.tests {
+            println!("{},{},{},{},{},{},{},{},{},{}",
+                self.os, csv_quote(&self.version), csv_quote(&self.renderer),
+                self.width, self.height, self.hidpi,
+                csv_quote(&test.name), test.fullscreen_ns, test.megapixel_ns, test.num_draws,
+            );
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Captures of the rendered frames
+
+use std::fmt;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use gl;
+use gl::types::*;
+use png;
+
+/// RGBA8 pixels of a frame, with the rows in GL's bottom-up order.
+pub struct Frame {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Frame {
+    /// Reads the whole of the bound framebuffer, of the given size.
+    pub fn read(width: u32, height: u32) -> Self {
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        unsafe {
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(0, 0, width as GLsizei, height as GLsizei,
+                gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut _);
+        }
+        Frame { width, height, pixels }
+    }
+
+    /// Writes the frame as a PNG image, flipping it upright.
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let error = |e: &dyn fmt::Display| format!("failed to write '{}': {}", path.display(), e);
+        let row_size = self.width as usize * 4;
+        let upright = self.pixels
+            .chunks(row_size)
+            .rev()
+            .flat_map(|row| row.iter().cloned())
+            .collect::<Vec<_>>();
+
+        let file = File::create(path).map_err(|e| error(&e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+        writer.write_image_data(&upright).map_err(|e| error(&e))
+    }
+}
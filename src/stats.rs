@@ -0,0 +1,149 @@
+//! Statistics over the per-frame query results
+
+use std::convert::TryFrom;
+use std::str::FromStr;
+
+/// Middle value of the samples, averaging the two middle
+/// ones when the count is even.
+pub fn median(samples: &[u64]) -> u64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Sample standard deviation, using Bessel's correction.
+pub fn std_dev(samples: &[u64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let mean = samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+    let sum_squares = samples
+        .iter()
+        .map(|&sample| (sample as f64 - mean).powi(2))
+        .sum::<f64>();
+    (sum_squares / (samples.len() - 1) as f64).sqrt()
+}
+
+/// Value below which `percent` of the samples fall, linearly
+/// interpolated between the two closest ranks. The samples must
+/// already be sorted.
+pub fn percentile(sorted: &[u64], percent: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = percent / 100.0 * (sorted.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    let fraction = rank - lo as f64;
+    sorted[lo] as f64 + (sorted[hi] as f64 - sorted[lo] as f64) * fraction
+}
+
+/// Time of `ns` over `pixel_count` pixels, scaled to a million of them.
+/// The product goes through `u128`, as it overflows `u64` for draws
+/// slower than about five hours per million pixels, and a time too
+/// long for `u64` saturates.
+pub fn per_megapixel(ns: u64, pixel_count: u64) -> u64 {
+    u64::try_from(ns as u128 * 1_000_000 / pixel_count as u128).unwrap_or(u64::MAX)
+}
+
+/// Counts of the samples falling in each of `bins` equal ranges between
+/// the smallest and the largest of them, along with that smallest sample
+/// and the width of the ranges.
+pub fn histogram(samples: &[u64], bins: usize) -> (Vec<usize>, u64, f64) {
+    let mut counts = vec![0; bins];
+    let (min, max) = match (samples.iter().min(), samples.iter().max()) {
+        (Some(&min), Some(&max)) => (min, max),
+        _ => return (counts, 0, 0.0),
+    };
+    let width = (max - min) as f64 / bins as f64;
+    for &sample in samples {
+        let bin = if width > 0.0 { ((sample - min) as f64 / width) as usize } else { 0 };
+        counts[bin.min(bins - 1)] += 1;
+    }
+    (counts, min, width)
+}
+
+/// How the noisy frames are excluded from the measurements.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutlierFilter {
+    /// Drop the configured warmup and cooldown frames.
+    Warmup,
+    /// Drop the frames outside of `[Q1 - 1.5 * IQR, Q3 + 1.5 * IQR]`.
+    Iqr,
+}
+
+impl FromStr for OutlierFilter {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "warmup" => Ok(OutlierFilter::Warmup),
+            "iqr" => Ok(OutlierFilter::Iqr),
+            _ => Err(format!("unknown outlier filter '{}'", s)),
+        }
+    }
+}
+
+/// Keeps the samples within 1.5 interquartile ranges of the
+/// first and third quartiles, preserving their order.
+pub fn filter_iqr(samples: &[u64]) -> Vec<u64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort();
+    let q1 = percentile(&sorted, 25.0);
+    let q3 = percentile(&sorted, 75.0);
+    let iqr = q3 - q1;
+    let (lo, hi) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+    samples
+        .iter()
+        .cloned()
+        .filter(|&sample| lo <= sample as f64 && sample as f64 <= hi)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_iqr_drops_the_outliers_in_order() {
+        let samples = [12, 10, 11, 13, 500, 11, 12, 10];
+        assert_eq!(filter_iqr(&samples), vec![12, 10, 11, 13, 11, 12, 10]);
+    }
+
+    #[test]
+    fn per_megapixel_does_not_overflow() {
+        assert_eq!(per_megapixel(u64::MAX / 2, 1), u64::MAX);
+        assert_eq!(per_megapixel(u64::MAX / 2, 1_000_000), u64::MAX / 2);
+    }
+
+    #[test]
+    fn percentile_of_exact_ranks() {
+        let sorted = [10, 20, 30, 40, 50];
+        assert_eq!(percentile(&sorted, 25.0), 20.0);
+        assert_eq!(percentile(&sorted, 50.0), 30.0);
+    }
+
+    #[test]
+    fn percentile_interpolates_between_ranks() {
+        let sorted = [10, 20, 30, 40];
+        assert_eq!(percentile(&sorted, 50.0), 25.0);
+        assert!((percentile(&sorted, 90.0) - 37.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn percentile_of_the_bounds() {
+        let sorted = [3, 7, 11];
+        assert_eq!(percentile(&sorted, 0.0), 3.0);
+        assert_eq!(percentile(&sorted, 100.0), 11.0);
+    }
+
+    #[test]
+    fn per_megapixel_matches_the_u64_formula() {
+        let (ns, pixel_count) = (3_456_789, 1920 * 1080);
+        assert_eq!(per_megapixel(ns, pixel_count), ns * 1_000_000 / pixel_count);
+    }
+}
@@ -0,0 +1,223 @@
+//! Per-frame timing of the GPU work
+
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
+
+use gl;
+use gl::types::*;
+
+use Flags;
+
+/// How the GPU work of a frame is timed.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Timing {
+    /// One `GL_TIME_ELAPSED` query around the timed region.
+    Elapsed,
+    /// `GL_TIMESTAMP` queries before the clear, between the clear and
+    /// the draw, and after the draw, so both are known separately.
+    Timestamp,
+}
+
+impl FromStr for Timing {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "elapsed" => Ok(Timing::Elapsed),
+            "timestamp" => Ok(Timing::Timestamp),
+            _ => Err(format!("unknown timing '{}'", s)),
+        }
+    }
+}
+
+/// Frames whose queries are in flight at once. The results of a frame
+/// are read when its queries are about to be reused, so they lag this
+/// many frames behind, which is plenty for the GPU to have produced
+/// them and doesn't leave a backlog to wait for after the last frame.
+pub const QUERY_RING_FRAMES: usize = 4;
+
+/// Number of query objects a frame needs with the given timing.
+pub fn queries_per_frame(timing: Timing) -> usize {
+    match timing {
+        Timing::Elapsed => 1,
+        Timing::Timestamp => 3,
+    }
+}
+
+/// Durations of every frame, in nanoseconds.
+pub struct FrameTimes {
+    /// Duration of the region selected by the test flags.
+    pub frames: Vec<u64>,
+    /// Durations of the clear and of the draw, with timestamp timing.
+    pub splits: Option<(Vec<u64>, Vec<u64>)>,
+    /// Time the CPU spent waiting for the query results to be available.
+    pub wait_ns: u64,
+}
+
+/// Measures the region of every frame selected by the test flags:
+/// the clear, the draw, or both of them. The CPU fallback, for
+/// contexts without timer queries, reads the clock around a
+/// `glFinish` on both ends of the region.
+///
+/// The queries form a ring of `QUERY_RING_FRAMES` frames, which
+/// the results are read from as the frames get reused.
+pub struct FrameTimer<'a> {
+    queries: &'a [GLuint],
+    timing: Timing,
+    cpu: bool,
+    flags: Flags,
+    /// Frames issued so far.
+    frame: usize,
+    start: Instant,
+    cpu_samples: Vec<u64>,
+    /// Results of the queries of the frames read back so far, in order.
+    results: Vec<u64>,
+    wait_ns: u64,
+}
+
+impl<'a> FrameTimer<'a> {
+    pub fn new(queries: &'a [GLuint], timing: Timing, cpu: bool, flags: Flags) -> Self {
+        FrameTimer {
+            queries,
+            timing,
+            cpu,
+            flags,
+            frame: 0,
+            start: Instant::now(),
+            cpu_samples: Vec::new(),
+            results: Vec::new(),
+            wait_ns: 0,
+        }
+    }
+
+    /// Queries of the given frame, within the ring.
+    fn frame_queries(&self, frame: usize) -> &'a [GLuint] {
+        let count = queries_per_frame(self.timing);
+        let start = frame % QUERY_RING_FRAMES * count;
+        &self.queries[start .. start + count]
+    }
+
+    fn begin(&mut self) {
+        if self.cpu {
+            unsafe { gl::Finish() };
+            self.start = Instant::now();
+        } else {
+            unsafe { gl::BeginQuery(gl::TIME_ELAPSED, self.frame_queries(self.frame)[0]) };
+        }
+    }
+
+    fn end(&mut self) {
+        if self.cpu {
+            unsafe { gl::Finish() };
+            self.cpu_samples.push(self.start.elapsed().as_nanos() as u64);
+        } else {
+            unsafe { gl::EndQuery(gl::TIME_ELAPSED) };
+        }
+    }
+
+    fn timestamp(&self, index: usize) {
+        unsafe { gl::QueryCounter(self.frame_queries(self.frame)[index], gl::TIMESTAMP) };
+    }
+
+    fn is_timestamp(&self) -> bool {
+        !self.cpu && self.timing == Timing::Timestamp
+    }
+
+    pub fn before_clear(&mut self) {
+        if self.is_timestamp() {
+            self.timestamp(0);
+        } else if self.flags.contains(Flags::CLEAR) {
+            self.begin();
+        }
+    }
+
+    pub fn after_clear(&mut self) {
+        if self.is_timestamp() {
+            self.timestamp(1);
+            return;
+        }
+        if !self.flags.contains(Flags::CLEAR) {
+            self.begin();
+        }
+        if !self.flags.contains(Flags::DRAW) {
+            self.end();
+        }
+    }
+
+    pub fn after_draw(&mut self) {
+        if self.is_timestamp() {
+            self.timestamp(2);
+        } else if self.flags.contains(Flags::DRAW) {
+            self.end();
+        }
+        self.frame += 1;
+        // the next frame reuses the queries of the oldest one in flight
+        if !self.cpu && self.frame >= QUERY_RING_FRAMES {
+            self.read_frame(self.frame - QUERY_RING_FRAMES);
+        }
+    }
+
+    /// Query objects used by the frames issued so far.
+    pub fn issued_queries(&self) -> &[GLuint] {
+        let frames = self.frame.min(QUERY_RING_FRAMES);
+        &self.queries[.. frames * queries_per_frame(self.timing)]
+    }
+
+    /// Waits for the results of the queries of a frame to be available,
+    /// and reads them. Reading a result that isn't would block, or with
+    /// some drivers return stale data.
+    fn read_frame(&mut self, frame: usize) {
+        let start = Instant::now();
+        for &query in self.frame_queries(frame) {
+            loop {
+                let mut available = 0;
+                unsafe { gl::GetQueryObjectuiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available) };
+                if available != 0 {
+                    break;
+                }
+                thread::yield_now();
+            }
+            let mut result = 0;
+            unsafe { gl::GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut result) };
+            self.results.push(result);
+        }
+        self.wait_ns += start.elapsed().as_nanos() as u64;
+    }
+
+    /// Collects the durations of all the frames issued so far, reading
+    /// the results of the ones still in flight.
+    pub fn results(mut self) -> FrameTimes {
+        if self.cpu {
+            return FrameTimes { frames: self.cpu_samples, splits: None, wait_ns: 0 };
+        }
+        let read = self.results.len() / queries_per_frame(self.timing);
+        for frame in read .. self.frame {
+            self.read_frame(frame);
+        }
+        let wait_ns = self.wait_ns;
+        if self.timing == Timing::Elapsed {
+            return FrameTimes { frames: self.results, splits: None, wait_ns };
+        }
+
+        // timestamps out of order, as after a disjoint event, count as 0
+        let stamps = std::mem::take(&mut self.results);
+        let clears = stamps.chunks(3).map(|t| t[1].saturating_sub(t[0])).collect::<Vec<_>>();
+        let draws = stamps.chunks(3).map(|t| t[2].saturating_sub(t[1])).collect::<Vec<_>>();
+        let frames = clears
+            .iter()
+            .zip(&draws)
+            .map(|(&clear, &draw)| {
+                let mut total = 0;
+                if self.flags.contains(Flags::CLEAR) {
+                    total += clear;
+                }
+                if self.flags.contains(Flags::DRAW) {
+                    total += draw;
+                }
+                total
+            })
+            .collect();
+        FrameTimes { frames, splits: Some((clears, draws)), wait_ns }
+    }
+}